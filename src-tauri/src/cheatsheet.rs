@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use crate::input_capture::InputEventPayload;
+use crate::transcript::label_content;
+
+/// A distinct shortcut used during the session.
+struct ShortcutUse<'a> {
+    combo: String,
+    name: Option<&'a str>,
+    count: u64,
+}
+
+/// Output format, picked from the export path's extension.
+#[derive(Clone, Copy, PartialEq)]
+pub enum CheatsheetFormat {
+    Markdown,
+    Html,
+}
+
+impl CheatsheetFormat {
+    /// `.html`/`.htm` exports HTML; anything else Markdown.
+    pub fn for_path(path: &str) -> Self {
+        let lower = path.to_ascii_lowercase();
+        if lower.ends_with(".html") || lower.ends_with(".htm") {
+            Self::Html
+        } else {
+            Self::Markdown
+        }
+    }
+}
+
+/// Distinct shortcuts, most used first, named from `names` (see
+/// `shortcut_names`). Only `shortcut` events carrying `modifier_hold_ms`
+/// count, which excludes the preview.
+fn collect_shortcuts<'a>(
+    events: &[InputEventPayload],
+    names: &'a HashMap<String, String>,
+) -> Vec<ShortcutUse<'a>> {
+    let mut counts: HashMap<&str, u64> = HashMap::new();
+    for event in events {
+        if event.event_type == "shortcut" && event.modifier_hold_ms.is_some() {
+            *counts.entry(label_content(&event.label)).or_insert(0) += 1;
+        }
+    }
+    let mut uses: Vec<ShortcutUse> = counts
+        .into_iter()
+        .map(|(combo, count)| ShortcutUse {
+            combo: combo.to_string(),
+            name: names.get(combo).map(String::as_str),
+            count,
+        })
+        .collect();
+    uses.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.combo.cmp(&b.combo)));
+    uses
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Markdown table cells can't contain a bare `|`.
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn render_markdown(known: &[&ShortcutUse], other: &[&ShortcutUse]) -> String {
+    let mut out = String::from("# Shortcut cheat sheet\n");
+    if !known.is_empty() {
+        out.push_str("\n## Common actions\n\n| Shortcut | Action | Uses |\n| --- | --- | --- |\n");
+        for shortcut in known {
+            out.push_str(&format!(
+                "| `{}` | {} | {} |\n",
+                escape_markdown(&shortcut.combo),
+                shortcut.name.unwrap_or_default(),
+                shortcut.count
+            ));
+        }
+    }
+    if !other.is_empty() {
+        out.push_str("\n## Other shortcuts\n\n| Shortcut | Uses |\n| --- | --- |\n");
+        for shortcut in other {
+            out.push_str(&format!(
+                "| `{}` | {} |\n",
+                escape_markdown(&shortcut.combo),
+                shortcut.count
+            ));
+        }
+    }
+    out
+}
+
+fn render_html(known: &[&ShortcutUse], other: &[&ShortcutUse]) -> String {
+    let mut out = String::from(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Shortcut cheat sheet</title></head>\n<body>\n<h1>Shortcut cheat sheet</h1>\n",
+    );
+    if !known.is_empty() {
+        out.push_str("<h2>Common actions</h2>\n<table>\n<tr><th>Shortcut</th><th>Action</th><th>Uses</th></tr>\n");
+        for shortcut in known {
+            out.push_str(&format!(
+                "<tr><td><kbd>{}</kbd></td><td>{}</td><td>{}</td></tr>\n",
+                escape_html(&shortcut.combo),
+                shortcut.name.unwrap_or_default(),
+                shortcut.count
+            ));
+        }
+        out.push_str("</table>\n");
+    }
+    if !other.is_empty() {
+        out.push_str(
+            "<h2>Other shortcuts</h2>\n<table>\n<tr><th>Shortcut</th><th>Uses</th></tr>\n",
+        );
+        for shortcut in other {
+            out.push_str(&format!(
+                "<tr><td><kbd>{}</kbd></td><td>{}</td></tr>\n",
+                escape_html(&shortcut.combo),
+                shortcut.count
+            ));
+        }
+        out.push_str("</table>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// Renders the distinct shortcuts in `events` as a cheat sheet, with those in
+/// `names` (Copy, Paste, ...) listed under their action. `None` when the
+/// events contain no shortcuts.
+pub fn render_cheatsheet(
+    events: &[InputEventPayload],
+    names: &HashMap<String, String>,
+    format: CheatsheetFormat,
+) -> Option<String> {
+    let uses = collect_shortcuts(events, names);
+    if uses.is_empty() {
+        return None;
+    }
+    let (known, other): (Vec<&ShortcutUse>, Vec<&ShortcutUse>) =
+        uses.iter().partition(|u| u.name.is_some());
+    Some(match format {
+        CheatsheetFormat::Markdown => render_markdown(&known, &other),
+        CheatsheetFormat::Html => render_html(&known, &other),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shortcuts::shortcut_names;
+    use crate::state::{Hotkey, ModifierStyle, Settings};
+
+    fn shortcut(label: &str) -> InputEventPayload {
+        let mut event = InputEventPayload::new("shortcut", label, 0);
+        event.modifier_hold_ms = Some(HashMap::new());
+        event
+    }
+
+    #[test]
+    fn configured_names_group_styled_shortcuts() {
+        let settings = Settings {
+            modifier_style: ModifierStyle::MacSymbols,
+            shortcut_labels: vec![(Hotkey::parse("Ctrl+K").unwrap(), "Link".to_string())],
+            ..Settings::default()
+        };
+        let events = [
+            shortcut("@Key[⌃+K]"),
+            shortcut("@Key[⌃+K]"),
+            shortcut("@Key[⌃+J]"),
+        ];
+        let sheet = render_cheatsheet(
+            &events,
+            &shortcut_names(&settings),
+            CheatsheetFormat::Markdown,
+        )
+        .unwrap();
+        assert!(sheet.contains("| `⌃+K` | Link | 2 |"), "{}", sheet);
+        assert!(sheet.contains("| `⌃+J` | 1 |"), "{}", sheet);
+    }
+
+    #[test]
+    fn previews_and_empty_sessions_give_no_sheet() {
+        let preview = InputEventPayload::new("shortcut", "@Key[Ctrl+C (Copy)]", 0);
+        assert_eq!(
+            render_cheatsheet(&[preview], &HashMap::new(), CheatsheetFormat::Html),
+            None
+        );
+    }
+}
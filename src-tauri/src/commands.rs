@@ -1,31 +1,1668 @@
-use tauri::{command, AppHandle, Manager};
+use chrono::NaiveTime;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{command, AppHandle, LogicalSize, Manager, State};
 
+use crate::cheatsheet::{render_cheatsheet, CheatsheetFormat};
+use crate::config;
+use crate::event_server::EventServer;
+use crate::idle;
+use crate::input_capture::{
+    apply_label_format, button_name, emit_event, emit_system, end_listener, get_timestamp,
+    parse_button_name, parse_key_name, start_capture, window_rect_to_screen, InputEventPayload,
+    EVENT_TYPES,
+};
+use crate::logging;
+use crate::pen::{self, PenSample};
+use crate::recording::{self, emit_recording_state};
+use crate::shortcuts::{preview_labels, shortcut_names};
+use crate::state::{
+    CaptureState, CoordinateDisplay, Corner, Hotkey, KeyRepeatMode, KeyboardLayout, Modifier,
+    ModifierStyle, NameConflict, NameConflictPolicy, OverlayAnchor, Rect, ScheduleWindow, Settings,
+    TimestampMode,
+};
+use crate::transcript::render_transcript;
+
+/// On macOS, whether the app is trusted for accessibility. Windows has no such
+/// permission; there the low-level hook is reported instead, since it stops
+/// working without an error when UIPI blocks it.
 #[command]
-pub fn check_accessibility_permission() -> bool {
+#[cfg_attr(not(target_os = "windows"), allow(unused_variables))]
+pub fn check_accessibility_permission(state: State<'_, Arc<CaptureState>>) -> bool {
     #[cfg(target_os = "macos")]
-    {
-        macos_accessibility_client::accessibility::application_is_trusted()
+    let trusted = macos_accessibility_client::accessibility::application_is_trusted();
+    #[cfg(target_os = "windows")]
+    let trusted = health(&state).hook_responding;
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let trusted = true;
+    log::info!("Accessibility permission check: trusted={}", trusted);
+    trusted
+}
+
+/// An rdev hook that is installed but has delivered nothing for this long is
+/// reported as not responding. Any mouse movement produces events, so this
+/// only triggers when the hook is blocked (e.g. by an elevated foreground app
+/// on Windows) or the user is away.
+const HOOK_SILENCE_MS: u64 = 60_000;
+
+/// Whether input capture is working, for the UI to poll.
+#[derive(serde::Serialize)]
+pub struct CaptureHealth {
+    /// The rdev listener thread is running.
+    pub listener_running: bool,
+    /// Capture was turned off with `stop_capture`.
+    pub stopped: bool,
+    /// The listener has delivered an event within the last minute.
+    pub hook_responding: bool,
+    /// Milliseconds since the last event rdev delivered, if any.
+    pub ms_since_last_input: Option<u64>,
+    /// Why the listener stopped, if it did.
+    pub last_error: Option<String>,
+    /// Payloads dropped under load because the pipeline queue was full.
+    pub events_dropped: u64,
+}
+
+fn health(state: &CaptureState) -> CaptureHealth {
+    let listener_running = state.listener_running.load(Ordering::SeqCst);
+    let last_input_ms = state.last_input_ms.load(Ordering::Relaxed);
+    let ms_since_last_input =
+        (last_input_ms != 0).then(|| (get_timestamp() as u64).saturating_sub(last_input_ms));
+    CaptureHealth {
+        listener_running,
+        stopped: state.capture_stopped.load(Ordering::SeqCst),
+        hook_responding: listener_running
+            && ms_since_last_input.is_none_or(|ms| ms < HOOK_SILENCE_MS),
+        ms_since_last_input,
+        last_error: state.last_error.lock().unwrap().clone(),
+        events_dropped: state.events_dropped.load(Ordering::Relaxed),
     }
-    #[cfg(not(target_os = "macos"))]
-    {
-        true
+}
+
+#[command]
+pub fn capture_health(state: State<'_, Arc<CaptureState>>) -> CaptureHealth {
+    health(&state)
+}
+
+/// Whether global capture can work in this desktop session.
+#[derive(serde::Serialize)]
+pub struct CaptureBackendStatus {
+    /// `XDG_SESSION_TYPE` on Linux (`x11`, `wayland`, `tty`, ...), if set.
+    pub session_type: Option<String>,
+    /// Global capture is expected to deliver events.
+    pub supported: bool,
+    /// Why capture is unlikely to work, for the UI to show. `None` when supported.
+    pub message: Option<String>,
+}
+
+/// rdev listens through X11 on Linux; under Wayland the hook installs without
+/// an error but only sees input for XWayland windows, if anything. Reports
+/// that up front so the UI can warn instead of showing an empty overlay.
+#[command]
+pub fn capture_backend_status() -> CaptureBackendStatus {
+    #[cfg(target_os = "linux")]
+    let status = {
+        let session_type = std::env::var("XDG_SESSION_TYPE")
+            .ok()
+            .map(|s| s.to_lowercase())
+            .filter(|s| !s.is_empty());
+        let has_x11 = std::env::var_os("DISPLAY").is_some();
+        let is_wayland = session_type.as_deref() == Some("wayland")
+            || (session_type.is_none() && std::env::var_os("WAYLAND_DISPLAY").is_some());
+        let message = if is_wayland {
+            Some(if has_x11 {
+                "Wayland session: global input capture only sees XWayland apps. Log in with an X11 session for full capture.".to_string()
+            } else {
+                "Wayland session without XWayland: global input capture is not available. Log in with an X11 session.".to_string()
+            })
+        } else if !has_x11 {
+            Some(
+                "No X11 display found (DISPLAY is not set): global input capture is not available."
+                    .to_string(),
+            )
+        } else {
+            None
+        };
+        CaptureBackendStatus {
+            session_type,
+            supported: message.is_none(),
+            message,
+        }
+    };
+    #[cfg(not(target_os = "linux"))]
+    let status = CaptureBackendStatus {
+        session_type: None,
+        supported: true,
+        message: None,
+    };
+    if let Some(message) = &status.message {
+        log::warn!("Capture backend: {}", message);
     }
+    status
 }
 
 #[command]
 pub fn request_accessibility_permission() -> bool {
     #[cfg(target_os = "macos")]
+    let trusted = macos_accessibility_client::accessibility::application_is_trusted_with_prompt();
+    #[cfg(not(target_os = "macos"))]
+    let trusted = true;
+    log::info!("Accessibility permission requested: trusted={}", trusted);
+    trusted
+}
+
+#[command]
+pub fn set_ignore_cursor_events(app: AppHandle, ignore: bool) -> Result<(), String> {
+    let window = app
+        .get_webview_window("main")
+        .ok_or("No main window found")?;
+    window
+        .set_ignore_cursor_events(ignore)
+        .map_err(|e| e.to_string())
+}
+
+/// Resizes the overlay window, in logical pixels.
+#[command]
+pub fn set_overlay_size(app: AppHandle, width: f64, height: f64) -> Result<(), String> {
+    if !width.is_finite() || !height.is_finite() || width <= 0.0 || height <= 0.0 {
+        return Err(format!("Invalid overlay size: {}x{}", width, height));
+    }
+    let window = app
+        .get_webview_window("main")
+        .ok_or("No main window found")?;
+    window
+        .set_size(LogicalSize::new(width, height))
+        .map_err(|e| e.to_string())
+}
+
+/// Shows the overlay window if hidden and hides it otherwise. Returns whether it is now visible.
+pub(crate) fn toggle_overlay_visibility(app: &AppHandle) -> Result<bool, String> {
+    let window = app
+        .get_webview_window("main")
+        .ok_or("No main window found")?;
+    let visible = window.is_visible().map_err(|e| e.to_string())?;
+    if visible {
+        window.hide().map_err(|e| e.to_string())?;
+    } else {
+        window.show().map_err(|e| e.to_string())?;
+    }
+    Ok(!visible)
+}
+
+#[command]
+pub fn toggle_overlay(app: AppHandle) -> Result<bool, String> {
+    toggle_overlay_visibility(&app)
+}
+
+/// Sets how the overlay is positioned: `fixed` (stays where placed),
+/// `follow_cursor` (moves next to the cursor) or `follow_monitor` (moves to the
+/// cursor's monitor). Persisted.
+#[command]
+pub fn set_overlay_anchor(
+    app: AppHandle,
+    state: State<'_, Arc<CaptureState>>,
+    mode: String,
+) -> Result<(), String> {
+    let anchor =
+        OverlayAnchor::parse(&mode).ok_or_else(|| format!("Unknown overlay anchor '{}'", mode))?;
+    state.update_settings(|s| s.overlay_anchor = anchor);
+    config::save(&app, &state)
+}
+
+/// Sets the hot corner (`top-left`, `top-right`, `bottom-left`, `bottom-right`)
+/// that toggles the overlay when the cursor dwells in it. `None` disables it.
+#[command]
+pub fn set_hot_corner(
+    state: State<'_, Arc<CaptureState>>,
+    corner: Option<String>,
+) -> Result<(), String> {
+    let corner = match corner {
+        Some(value) => {
+            Some(Corner::parse(&value).ok_or_else(|| format!("Unknown corner '{}'", value))?)
+        }
+        None => None,
+    };
+    state.update_settings(|s| s.hot_corner = corner);
+    Ok(())
+}
+
+/// Makes the overlay click-through everywhere except the regions registered with
+/// `set_interactive_regions`, toggling as the cursor enters and leaves them.
+#[command]
+pub fn set_auto_click_through(state: State<'_, Arc<CaptureState>>, enabled: bool) {
+    state.update_settings(|s| s.auto_click_through = enabled);
+}
+
+/// Registers the overlay's interactive regions, given in webview (CSS pixel)
+/// coordinates as reported by `getBoundingClientRect`.
+#[command]
+pub fn set_interactive_regions(
+    app: AppHandle,
+    state: State<'_, Arc<CaptureState>>,
+    regions: Vec<Rect>,
+) -> Result<(), String> {
+    let screen_regions = regions_to_screen(&app, &state, regions)?;
+    state.update_settings(|s| s.interactive_regions = screen_regions);
+    Ok(())
+}
+
+/// Makes the overlay accept the cursor only inside one region (webview CSS
+/// pixels, e.g. a settings corner) and click-through everywhere else. Shorthand
+/// for `set_interactive_regions` with that region plus `set_auto_click_through`;
+/// takes effect on the next mouse move.
+#[command]
+pub fn set_ignore_cursor_events_region(
+    app: AppHandle,
+    state: State<'_, Arc<CaptureState>>,
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+) -> Result<(), String> {
+    if ![x, y, w, h].iter().all(|v| v.is_finite()) || w <= 0.0 || h <= 0.0 {
+        return Err(format!("Invalid region: {}x{} at ({}, {})", w, h, x, y));
+    }
+    let region = Rect {
+        x,
+        y,
+        width: w,
+        height: h,
+    };
+    let screen_regions = regions_to_screen(&app, &state, vec![region])?;
+    state.update_settings(|s| {
+        s.interactive_regions = screen_regions;
+        s.auto_click_through = true;
+    });
+    Ok(())
+}
+
+/// Converts webview rects to rdev screen coordinates for the main window's
+/// current position and scale.
+fn regions_to_screen(
+    app: &AppHandle,
+    state: &CaptureState,
+    regions: Vec<Rect>,
+) -> Result<Vec<Rect>, String> {
+    let window = app
+        .get_webview_window("main")
+        .ok_or("No main window found")?;
+    let origin = window.inner_position().map_err(|e| e.to_string())?;
+    let scale_factor = match state.settings().coordinate_scale_override {
+        Some(scale) => scale,
+        None => window.scale_factor().map_err(|e| e.to_string())?,
+    };
+    Ok(regions
+        .into_iter()
+        .map(|region| window_rect_to_screen(region, origin, scale_factor))
+        .collect())
+}
+
+/// Limits mouse events to a screen region (rdev screen coordinates); `None`
+/// clears it. With `filter_clicks`, clicks, drags and scrolls outside are
+/// dropped along with cursor moves.
+#[command]
+pub fn set_mouse_roi(
+    state: State<'_, Arc<CaptureState>>,
+    roi: Option<Rect>,
+    filter_clicks: Option<bool>,
+) -> Result<(), String> {
+    if let Some(roi) = roi {
+        if roi.width <= 0.0 || roi.height <= 0.0 {
+            return Err("Region of interest must have a positive size".to_string());
+        }
+    }
+    state.update_settings(|s| {
+        s.mouse_roi = roi;
+        if let Some(filter_clicks) = filter_clicks {
+            s.roi_filters_clicks = filter_clicks;
+        }
+    });
+    Ok(())
+}
+
+/// Forces the scale factor used to convert window/monitor coordinates into rdev
+/// screen space, for setups where the detected one is wrong. `None` restores
+/// auto-detection. Regions set earlier keep their old conversion until re-sent.
+#[command]
+pub fn set_coordinate_scale_override(
+    state: State<'_, Arc<CaptureState>>,
+    scale: Option<f64>,
+) -> Result<(), String> {
+    if let Some(scale) = scale {
+        if !scale.is_finite() || scale <= 0.0 {
+            return Err(format!("Invalid scale factor: {}", scale));
+        }
+    }
+    state.update_settings(|s| s.coordinate_scale_override = scale);
+    Ok(())
+}
+
+/// Selects the keyboard layout (an id from `list_keyboard_layouts`) whose table
+/// resolves keys the OS doesn't name; captured events carry it in `layout`.
+/// Persisted.
+#[command]
+pub fn set_keyboard_layout(
+    app: AppHandle,
+    state: State<'_, Arc<CaptureState>>,
+    layout: Option<String>,
+) -> Result<(), String> {
+    let layout = layout
+        .map(|id| {
+            KeyboardLayout::parse(&id).ok_or_else(|| format!("Unknown keyboard layout: {}", id))
+        })
+        .transpose()?;
+    state.update_settings(|s| s.keyboard_layout = layout);
+    config::save(&app, &state)
+}
+
+#[derive(serde::Serialize)]
+pub struct LayoutInfo {
+    pub id: &'static str,
+    pub name: &'static str,
+}
+
+/// Built-in keyboard layouts for the settings UI.
+#[command]
+pub fn list_keyboard_layouts() -> Vec<LayoutInfo> {
+    KeyboardLayout::ALL
+        .iter()
+        .map(|layout| LayoutInfo {
+            id: layout.id(),
+            name: layout.display_name(),
+        })
+        .collect()
+}
+
+/// Sets how far (1-200 rdev screen units) the cursor must move with a button
+/// held before it counts as a drag; raising it stops hand tremor from
+/// producing `@DragStart`. Default 10.
+#[command]
+pub fn set_drag_threshold(
+    app: AppHandle,
+    state: State<'_, Arc<CaptureState>>,
+    pixels: f64,
+) -> Result<(), String> {
+    if !(1.0..=200.0).contains(&pixels) {
+        return Err(format!(
+            "Drag threshold must be between 1 and 200 pixels: {}",
+            pixels
+        ));
+    }
+    state.update_settings(|s| s.drag_threshold_px = pixels);
+    config::save(&app, &state)
+}
+
+/// Sets a separate drag threshold for touch input (`None` uses the mouse one).
+/// rdev doesn't currently report the pointer source, so until it does all
+/// captured input uses the mouse threshold.
+#[command]
+pub fn set_touch_drag_threshold(
+    state: State<'_, Arc<CaptureState>>,
+    px: Option<f64>,
+) -> Result<(), String> {
+    if let Some(px) = px {
+        if !px.is_finite() || px <= 0.0 {
+            return Err(format!("Invalid drag threshold: {}", px));
+        }
+    }
+    state.update_settings(|s| s.touch_drag_threshold_px = px);
+    Ok(())
+}
+
+/// Opt-in `@KeyUp[...]` events carrying the hold duration of each released key.
+#[command]
+pub fn set_emit_key_release(state: State<'_, Arc<CaptureState>>, enabled: bool) {
+    state.update_settings(|s| s.emit_key_release = enabled);
+}
+
+/// Minimum hold in ms before a released key is shown as `@KeyHold[...]`; `0` disables it.
+#[command]
+pub fn set_key_hold_threshold(state: State<'_, Arc<CaptureState>>, ms: u64) {
+    state.update_settings(|s| s.key_hold_threshold_ms = ms);
+}
+
+/// How auto-repeat of a held key is shown: `show` (default), `suppress` or `count`.
+#[command]
+pub fn set_key_repeat_mode(
+    state: State<'_, Arc<CaptureState>>,
+    mode: String,
+) -> Result<(), String> {
+    let repeat_mode =
+        KeyRepeatMode::parse(&mode).ok_or_else(|| format!("Unknown key repeat mode '{}'", mode))?;
+    state.update_settings(|s| s.key_repeat_mode = repeat_mode);
+    Ok(())
+}
+
+/// Shows modifier taps (a modifier pressed and released on its own) as `@Key[Shift]`.
+#[command]
+pub fn set_show_lone_modifiers(state: State<'_, Arc<CaptureState>>, enabled: bool) {
+    state.update_settings(|s| s.show_lone_modifiers = enabled);
+}
+
+/// Labels modifiers by side, e.g. `@Key[RCtrl+C]`, instead of plain `Ctrl`.
+#[command]
+pub fn set_distinguish_modifier_side(state: State<'_, Arc<CaptureState>>, enabled: bool) {
+    state.update_settings(|s| s.distinguish_modifier_side = enabled);
+}
+
+/// Masks typed characters as `@Key[•]` while enabled. `mask` optionally
+/// replaces the mask character.
+#[command]
+pub fn set_privacy_mode(
+    state: State<'_, Arc<CaptureState>>,
+    enabled: bool,
+    mask: Option<String>,
+) -> Result<(), String> {
+    if let Some(mask) = &mask {
+        if mask.chars().count() != 1 {
+            return Err(format!("Privacy mask must be a single character: {}", mask));
+        }
+    }
+    state.update_settings(|s| {
+        s.privacy_mode = enabled;
+        if let Some(mask) = mask {
+            s.privacy_mask = mask;
+        }
+    });
+    Ok(())
+}
+
+/// Sets the overlay label template, e.g. `"{type}: {value}"` for `Key: Ctrl+S`
+/// instead of `@Key[Ctrl+S]`. It must contain `{value}`; the default is
+/// `"@{type}[{value}]"`. The overlay's own grouping of typing and clicks relies
+/// on the default form.
+#[command]
+pub fn set_label_format(
+    state: State<'_, Arc<CaptureState>>,
+    template: String,
+) -> Result<(), String> {
+    if !template.contains("{value}") {
+        return Err(format!("Label format must contain {{value}}: {}", template));
+    }
+    state.update_settings(|s| s.label_format = template);
+    Ok(())
+}
+
+/// Turns the once-per-second `@Stats[N KPM, N CPM]` activity meter on or off.
+#[command]
+pub fn set_stats_enabled(state: State<'_, Arc<CaptureState>>, enabled: bool) {
+    state.update_settings(|s| s.stats_enabled = enabled);
+}
+
+#[derive(serde::Deserialize)]
+pub struct ScheduleWindowSpec {
+    start: String,
+    end: String,
+}
+
+fn parse_schedule_time(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value, "%H:%M")
+        .map_err(|_| format!("Invalid time '{}', expected HH:MM", value))
+}
+
+/// Sets the daily quiet hours (local `HH:MM` times) during which capture is
+/// paused automatically. An empty list disables the schedule.
+#[command]
+pub fn set_capture_schedule(
+    state: State<'_, Arc<CaptureState>>,
+    windows: Vec<ScheduleWindowSpec>,
+) -> Result<(), String> {
+    let schedule = windows
+        .iter()
+        .map(|w| {
+            Ok(ScheduleWindow {
+                start: parse_schedule_time(&w.start)?,
+                end: parse_schedule_time(&w.end)?,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    state.update_settings(|s| s.capture_schedule = schedule);
+    Ok(())
+}
+
+/// Sets the keys that never emit events. Names are resolved like `A`, `F5`,
+/// `CapsLock`, `Function` or `Unknown(code)`; unknown names are rejected.
+#[command]
+pub fn set_muted_keys(
+    state: State<'_, Arc<CaptureState>>,
+    keys: Vec<String>,
+) -> Result<(), String> {
+    let muted = keys
+        .iter()
+        .map(|name| parse_key_name(name).ok_or_else(|| format!("Unknown key '{}'", name)))
+        .collect::<Result<HashSet<_>, String>>()?;
+    state.update_settings(|s| s.muted_keys = muted);
+    Ok(())
+}
+
+#[command]
+pub fn get_prefer_os_name(state: State<'_, Arc<CaptureState>>) -> bool {
+    state.settings().prefer_os_name
+}
+
+/// Chooses between rdev's OS-provided key names (layout-aware, but unreliable on
+/// some platforms) and the built-in mapping tables for printable keys.
+#[command]
+pub fn set_prefer_os_name(state: State<'_, Arc<CaptureState>>, enabled: bool) {
+    state.update_settings(|s| s.prefer_os_name = enabled);
+}
+
+/// Sets the order modifiers appear in key labels, e.g. `["Meta", "Ctrl", "Alt", "Shift"]`.
+/// Must list each modifier exactly once; `None` restores the platform convention.
+#[command]
+pub fn set_modifier_order(
+    state: State<'_, Arc<CaptureState>>,
+    order: Option<Vec<String>>,
+) -> Result<(), String> {
+    let order = match order {
+        Some(names) => {
+            let order = names
+                .iter()
+                .map(|name| {
+                    Modifier::parse(name).ok_or_else(|| format!("Unknown modifier '{}'", name))
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            // Four entries covering all modifiers means each appears exactly once
+            if order.len() != 4
+                || Modifier::platform_order()
+                    .iter()
+                    .any(|m| !order.contains(m))
+            {
+                return Err(
+                    "Modifier order must list Ctrl, Alt, Shift and Meta exactly once".into(),
+                );
+            }
+            order
+        }
+        None => Modifier::platform_order(),
+    };
+    state.update_settings(|s| s.modifier_order = order);
+    Ok(())
+}
+
+/// Sets how modifiers are written: `text` (`Ctrl+S`, the default),
+/// `mac-symbols` (`⌘+S`) or `win` (`Win+S`).
+#[command]
+pub fn set_modifier_style(
+    state: State<'_, Arc<CaptureState>>,
+    style: String,
+) -> Result<(), String> {
+    let modifier_style = ModifierStyle::parse(&style)
+        .ok_or_else(|| format!("Unknown modifier style '{}'", style))?;
+    state.update_settings(|s| s.modifier_style = modifier_style);
+    Ok(())
+}
+
+/// Adds a `delta_ms` field (time since the previous emitted event) to payloads.
+#[command]
+pub fn set_include_delta(state: State<'_, Arc<CaptureState>>, enabled: bool) {
+    state.update_settings(|s| s.include_delta = enabled);
+}
+
+/// Adds a `raw` field with the rdev key or button (e.g. `KeyA`, `Unknown(135)`)
+/// to key and mouse button payloads, for triaging wrong-character reports.
+#[command]
+pub fn set_include_raw(state: State<'_, Arc<CaptureState>>, enabled: bool) {
+    state.update_settings(|s| s.include_raw = enabled);
+}
+
+/// Sets how long events are dropped after capture starts. Takes effect the
+/// next time capture starts.
+#[command]
+pub fn set_startup_settling_ms(state: State<'_, Arc<CaptureState>>, ms: u64) {
+    state.update_settings(|s| s.startup_settling_ms = ms);
+}
+
+/// Sets the inter-key gap below which keystrokes are reported as a paste. `0` disables it.
+#[command]
+pub fn set_paste_threshold_ms(state: State<'_, Arc<CaptureState>>, ms: u64) {
+    state.update_settings(|s| s.paste_threshold_ms = ms);
+}
+
+/// Assembles typed words into `@Type[hello]` events, shown once typing pauses
+/// or another key interrupts.
+#[command]
+pub fn set_text_aggregation(state: State<'_, Arc<CaptureState>>, enabled: bool) {
+    state.update_settings(|s| s.text_aggregation = enabled);
+}
+
+/// Enables or disables per-key press counting. Existing counts are kept.
+#[command]
+pub fn set_key_heatmap_enabled(state: State<'_, Arc<CaptureState>>, enabled: bool) {
+    state.update_settings(|s| s.key_heatmap = enabled);
+}
+
+/// Chooses which label wins when the OS key name and the built-in mapping
+/// table disagree: `os` (default), `manual`, or `both` (shown as `os/manual`).
+#[command]
+pub fn set_name_conflict_policy(
+    state: State<'_, Arc<CaptureState>>,
+    policy: String,
+) -> Result<(), String> {
+    let policy = NameConflictPolicy::parse(&policy)
+        .ok_or_else(|| format!("Unknown name conflict policy '{}'", policy))?;
+    state.update_settings(|s| s.name_conflict_policy = policy);
+    Ok(())
+}
+
+/// Keys whose OS name disagreed with the mapping table, most frequent first.
+/// Only key names and the characters they produced, never typed sequences.
+#[command]
+pub fn get_name_conflicts(state: State<'_, Arc<CaptureState>>) -> Vec<NameConflict> {
+    let mut conflicts: Vec<NameConflict> = state
+        .name_conflicts
+        .lock()
+        .unwrap()
+        .values()
+        .cloned()
+        .collect();
+    conflicts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+    conflicts
+}
+
+#[command]
+pub fn reset_name_conflicts(state: State<'_, Arc<CaptureState>>) {
+    state.name_conflicts.lock().unwrap().clear();
+}
+
+/// Press count per key name (e.g. "A", "Space") since the last reset.
+#[command]
+pub fn get_key_heatmap(state: State<'_, Arc<CaptureState>>) -> HashMap<String, u64> {
+    state.key_heatmap.lock().unwrap().clone()
+}
+
+#[command]
+pub fn reset_key_heatmap(state: State<'_, Arc<CaptureState>>) {
+    state.key_heatmap.lock().unwrap().clear();
+}
+
+/// Hides or shows `system` events in the overlay. The actions behind them
+/// (pausing, arming, ...) are unaffected.
+#[command]
+pub fn set_show_system_events(state: State<'_, Arc<CaptureState>>, enabled: bool) {
+    state.update_settings(|s| s.show_system_events = enabled);
+}
+
+/// Shows `@MouseMove` positions as `global` screen pixels (default), as
+/// `per-monitor` pixels, or as a `percent` of the monitor under the cursor.
+/// `pixels` is accepted for `global`.
+#[command]
+pub fn set_coordinate_mode(
+    state: State<'_, Arc<CaptureState>>,
+    mode: String,
+) -> Result<(), String> {
+    let display = CoordinateDisplay::parse(&mode)
+        .ok_or_else(|| format!("Unknown coordinate mode '{}'", mode))?;
+    state.update_settings(|s| s.coordinate_display = display);
+    Ok(())
+}
+
+/// Most decimal places accepted by `set_mouse_precision`.
+const MAX_MOUSE_PRECISION: u8 = 3;
+
+/// Sets the decimal places shown in `@MouseMove` coordinates (default 0).
+#[command]
+pub fn set_mouse_precision(
+    state: State<'_, Arc<CaptureState>>,
+    decimals: u8,
+) -> Result<(), String> {
+    if decimals > MAX_MOUSE_PRECISION {
+        return Err(format!(
+            "Mouse precision must be at most {} decimals",
+            MAX_MOUSE_PRECISION
+        ));
+    }
+    state.update_settings(|s| s.mouse_precision = decimals);
+    Ok(())
+}
+
+/// Snaps `@MouseMove` pixel positions to a `px` grid; `0` turns snapping off.
+#[command]
+pub fn set_mouse_grid(state: State<'_, Arc<CaptureState>>, px: u32) {
+    state.update_settings(|s| s.mouse_grid_px = px);
+}
+
+/// Hides passive `@MouseMove` for `ms` after each key press so jitter while
+/// typing doesn't clutter the overlay; `0` turns this off.
+#[command]
+pub fn set_suppress_move_while_typing(state: State<'_, Arc<CaptureState>>, ms: u64) {
+    state.update_settings(|s| s.suppress_move_while_typing_ms = ms);
+}
+
+/// Sets the clock payload timestamps use: `epoch_ms` (default), `monotonic_ms`
+/// (since launch) or `relative_ms` (since capture started). Recordings are
+/// written with the chosen clock.
+#[command]
+pub fn set_timestamp_mode(state: State<'_, Arc<CaptureState>>, mode: String) -> Result<(), String> {
+    let timestamp_mode =
+        TimestampMode::parse(&mode).ok_or_else(|| format!("Unknown timestamp mode '{}'", mode))?;
+    state.update_settings(|s| s.timestamp_mode = timestamp_mode);
+    Ok(())
+}
+
+/// Older name for `set_coordinate_mode`.
+#[command]
+pub fn set_coordinate_display(
+    state: State<'_, Arc<CaptureState>>,
+    mode: String,
+) -> Result<(), String> {
+    set_coordinate_mode(state, mode)
+}
+
+/// Configures dwell detection: `ms` of rest within `radius_px` emits `@Dwell`. `ms = 0` disables it.
+#[command]
+pub fn set_dwell(
+    state: State<'_, Arc<CaptureState>>,
+    ms: u64,
+    radius_px: f64,
+) -> Result<(), String> {
+    if !radius_px.is_finite() || radius_px < 0.0 {
+        return Err(format!("Invalid dwell radius: {}", radius_px));
+    }
+    state.update_settings(|s| {
+        s.dwell_ms = ms;
+        s.dwell_radius_px = radius_px;
+    });
+    Ok(())
+}
+
+/// Sends an `idle` event after `ms` without input so the overlay can hide;
+/// 0 disables it. Timeouts shorter than the watcher's poll interval are rejected.
+#[command]
+pub fn set_idle_timeout(state: State<'_, Arc<CaptureState>>, ms: u64) -> Result<(), String> {
+    let min_ms = idle::POLL_INTERVAL.as_millis() as u64;
+    if ms != 0 && ms < min_ms {
+        return Err(format!(
+            "Idle timeout must be 0 or at least {} ms: {}",
+            min_ms, ms
+        ));
+    }
+    state.update_settings(|s| s.idle_timeout_ms = ms);
+    Ok(())
+}
+
+/// Captures only the listed event types, e.g. `["click", "doubleclick", "drag"]`
+/// for mouse-only recordings. Types match exactly (`shortcut` is separate from
+/// `key`); an empty list allows everything. Pause and other system events are
+/// never filtered. Unknown types are rejected, since they would match nothing.
+#[command]
+pub fn set_event_filter(
+    state: State<'_, Arc<CaptureState>>,
+    allowed: Vec<String>,
+) -> Result<(), String> {
+    if let Some(event_type) = allowed
+        .iter()
+        .find(|event_type| !EVENT_TYPES.contains(&event_type.as_str()))
     {
-        macos_accessibility_client::accessibility::application_is_trusted_with_prompt()
+        return Err(format!("Unknown event type: {}", event_type));
     }
-    #[cfg(not(target_os = "macos"))]
+    state.update_settings(|s| s.event_filter = allowed);
+    Ok(())
+}
+
+/// Hides plain typing and shows only key presses made with Ctrl, Alt or Meta held.
+#[command]
+pub fn set_shortcut_only_mode(state: State<'_, Arc<CaptureState>>, enabled: bool) {
+    state.update_settings(|s| s.shortcut_only = enabled);
+}
+
+/// Configures "interesting events only" mode. `triggers` lists what opens the
+/// window: `"shortcut"` or event types like `"click"`, `"doubleclick"`, `"drag"`.
+#[command]
+pub fn set_interesting_filter(
+    state: State<'_, Arc<CaptureState>>,
+    enabled: bool,
+    window_ms: u64,
+    triggers: Vec<String>,
+) {
+    state.update_settings(|s| {
+        s.interesting_only = enabled;
+        s.interesting_window_ms = window_ms;
+        s.interesting_triggers = triggers;
+    });
+}
+
+/// Panic stop: disarms capture and hides the overlay in one step. The tray
+/// tooltip is the confirmation, since the overlay itself is gone.
+pub(crate) fn panic_stop(app: &AppHandle, state: &CaptureState) {
+    state.set_armed(false);
+    log::warn!("Panic hotkey: capture disarmed and overlay hidden");
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.hide();
+    }
+    if let Some(tray) = app.tray_by_id("tray") {
+        let _ = tray.set_tooltip(Some("EchoCast (capture stopped)"));
+    }
+    emit_system(app, state, "Capture Stopped (Panic)");
+}
+
+/// Which of the app's own hotkeys (`"pause"`, `"panic"`, `"clear"`) already
+/// uses `hotkey`'s combo, skipping `replacing`, the one being set.
+fn hotkey_taken_by(settings: &Settings, hotkey: &Hotkey, replacing: &str) -> Option<&'static str> {
+    [
+        ("pause", Some(&settings.pause_hotkey)),
+        ("panic", settings.panic_hotkey.as_ref()),
+        ("clear", settings.clear_hotkey.as_ref()),
+    ]
+    .into_iter()
+    .find(|(name, bound)| *name != replacing && bound.is_some_and(|b| b.same_combo(hotkey)))
+    .map(|(name, _)| name)
+}
+
+/// Sets the pause hotkey from modifier names (`Ctrl`, `Alt`, `Shift`, `Meta`)
+/// and a key name, e.g. `(["Ctrl", "Alt"], "P")`, the default.
+#[command]
+pub fn set_pause_hotkey(
+    app: AppHandle,
+    state: State<'_, Arc<CaptureState>>,
+    modifiers: Vec<String>,
+    key: String,
+) -> Result<(), String> {
+    if modifiers.is_empty() {
+        return Err("Pause hotkey needs at least one modifier".to_string());
+    }
+    let mut parts = modifiers;
+    parts.push(key);
+    let hotkey = Hotkey::parse(&parts.join("+"))?;
+    if let Some(other) = hotkey_taken_by(&state.settings(), &hotkey, "pause") {
+        return Err(format!("Pause hotkey is already the {} hotkey", other));
+    }
+    state.update_settings(|s| s.pause_hotkey = hotkey);
+    config::save(&app, &state)
+}
+
+/// Sets the panic hotkey, e.g. `"Ctrl+Alt+Shift+K"`. It must use at least two
+/// modifiers so it can't fire by accident. Off until set; `None` disables it.
+#[command]
+pub fn set_panic_hotkey(
+    state: State<'_, Arc<CaptureState>>,
+    hotkey: Option<String>,
+) -> Result<(), String> {
+    let hotkey = hotkey.as_deref().map(Hotkey::parse).transpose()?;
+    if let Some(hotkey) = &hotkey {
+        if hotkey.modifiers.len() < 2 {
+            return Err("Panic hotkey needs at least two modifiers".to_string());
+        }
+        if let Some(other) = hotkey_taken_by(&state.settings(), hotkey, "panic") {
+            return Err(format!("Panic hotkey is already the {} hotkey", other));
+        }
+    }
+    state.update_settings(|s| s.panic_hotkey = hotkey);
+    Ok(())
+}
+
+/// Sets the hotkey that clears the overlay, e.g. `"Ctrl+Alt+C"`. `None`
+/// disables it.
+#[command]
+pub fn set_clear_hotkey(
+    state: State<'_, Arc<CaptureState>>,
+    hotkey: Option<String>,
+) -> Result<(), String> {
+    let hotkey = hotkey.as_deref().map(Hotkey::parse).transpose()?;
+    if let Some(hotkey) = &hotkey {
+        if hotkey.modifiers.is_empty() {
+            return Err("Clear hotkey needs at least one modifier".to_string());
+        }
+        if let Some(other) = hotkey_taken_by(&state.settings(), hotkey, "clear") {
+            return Err(format!("Clear hotkey is already the {} hotkey", other));
+        }
+    }
+    state.update_settings(|s| s.clear_hotkey = hotkey);
+    Ok(())
+}
+
+/// Tells the overlay to drop everything it shows, with a `clear` event. The
+/// recent-events history is emptied too, so a reloaded overlay stays clear.
+pub(crate) fn emit_clear(app: &AppHandle, state: &CaptureState) {
+    log::info!("Overlay cleared");
+    state.recent_events.lock().unwrap().clear();
+    emit_event(app, state, InputEventPayload::now("clear", "Clear"));
+}
+
+#[command]
+pub fn clear_overlay(app: AppHandle, state: State<'_, Arc<CaptureState>>) {
+    emit_clear(&app, &state);
+}
+
+/// Pauses or resumes capture, like the pause hotkey. Reports the change with
+/// the same `Capture Paused`/`Capture Resumed` system event.
+pub(crate) fn set_paused_and_notify(app: &AppHandle, state: &CaptureState, paused: bool) {
+    if state.set_paused(paused) {
+        log::info!("Capture pause set by command: paused={}", paused);
+        if state.is_armed() {
+            let status_label = if paused { "Paused" } else { "Resumed" };
+            emit_system(app, state, &format!("Capture {}", status_label));
+        }
+    }
+}
+
+#[command]
+pub fn set_capture_paused(app: AppHandle, state: State<'_, Arc<CaptureState>>, paused: bool) {
+    set_paused_and_notify(&app, &state, paused);
+}
+
+#[command]
+pub fn arm_capture(app: AppHandle, state: State<'_, Arc<CaptureState>>) {
+    if state.set_armed(true) {
+        log::info!("Capture armed");
+        if let Some(tray) = app.tray_by_id("tray") {
+            let _ = tray.set_tooltip(Some("EchoCast"));
+        }
+        emit_system(&app, &state, "Capture Armed");
+    }
+}
+
+#[command]
+pub fn disarm_capture(app: AppHandle, state: State<'_, Arc<CaptureState>>) {
+    if state.set_armed(false) {
+        log::info!("Capture disarmed");
+        // Emitted directly since the capture thread stops emitting once disarmed
+        emit_system(&app, &state, "Capture Disarmed");
+    }
+}
+
+/// Turns input capture off entirely, unlike pause, which still tracks input
+/// and answers the pause hotkey. On Windows and macOS the listener thread is
+/// ended, removing the OS hook; on Linux (X11) rdev's hook can't be removed
+/// from outside, so it stays installed but returns straight away.
+#[command]
+pub fn stop_capture(app: AppHandle, state: State<'_, Arc<CaptureState>>) {
+    if state.capture_stopped.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    if end_listener(&state) {
+        log::info!("Ending the input listener");
+    }
+    // Their releases won't be seen while stopped
+    state.pressed_modifiers.lock().unwrap().clear();
+    state.pressed_buttons.lock().unwrap().clear();
+    log::info!("Capture stopped");
+    emit_system(&app, &state, "Capture Stopped");
+}
+
+/// Turns capture back on after `stop_capture`, starting a new listener if the
+/// stopped one was ended or the previous one gave up after repeated failures.
+#[command]
+pub fn start_capture_cmd(app: AppHandle, state: State<'_, Arc<CaptureState>>) {
+    let was_stopped = state.capture_stopped.swap(false, Ordering::SeqCst);
+    // A capture thread still winding down picks the flag back up itself
+    if !state.capture_thread_running.swap(true, Ordering::SeqCst) {
+        log::info!("Starting a new input capture thread");
+        *state.last_error.lock().unwrap() = None;
+        start_capture(app.clone(), state.inner().clone());
+    } else if !was_stopped {
+        return;
+    }
+    log::info!("Capture started");
+    emit_system(&app, &state, "Capture Started");
+}
+
+/// Sets the gap that ends a scroll gesture; longer inertial tails need a larger value.
+#[command]
+pub fn set_scroll_gesture_gap_ms(state: State<'_, Arc<CaptureState>>, ms: u64) {
+    state.update_settings(|s| s.scroll_gesture_gap_ms = ms);
+}
+
+/// Shows scroll gestures in notches of `px` pixels, e.g. `@Scroll[Down 3]`.
+/// `0` goes back to pixel distances (`@Scroll[Down 300px]`).
+#[command]
+pub fn set_scroll_step(state: State<'_, Arc<CaptureState>>, px: f64) -> Result<(), String> {
+    if !px.is_finite() || px < 0.0 {
+        return Err(format!(
+            "Scroll step must be a positive number of pixels: {}",
+            px
+        ));
+    }
+    state.update_settings(|s| s.scroll_step_px = (px > 0.0).then_some(px));
+    Ok(())
+}
+
+/// Longest accepted on-screen lifetime for an event type.
+const MAX_TTL_MS: u64 = 10 * 60 * 1000;
+
+/// Updates per-type on-screen lifetimes, e.g. `{"key": 5000, "mousemove": 500}`.
+/// Types not listed keep their current value; `0` removes a type's TTL so its
+/// events stay until pushed out. Unknown types are rejected.
+#[command]
+pub fn set_ttl_by_type(
+    state: State<'_, Arc<CaptureState>>,
+    ttl_by_type: HashMap<String, u64>,
+) -> Result<(), String> {
+    if let Some(event_type) = ttl_by_type
+        .keys()
+        .find(|event_type| !EVENT_TYPES.contains(&event_type.as_str()))
     {
-        true
+        return Err(format!("Unknown event type: {}", event_type));
+    }
+    if let Some((event_type, ms)) = ttl_by_type.iter().find(|(_, &ms)| ms > MAX_TTL_MS) {
+        return Err(format!(
+            "TTL for {} too long: {}ms (max {}ms)",
+            event_type, ms, MAX_TTL_MS
+        ));
     }
+    state.update_settings(|s| {
+        for (event_type, ms) in ttl_by_type {
+            if ms == 0 {
+                s.ttl_by_type.remove(&event_type);
+            } else {
+                s.ttl_by_type.insert(event_type, ms);
+            }
+        }
+    });
+    Ok(())
 }
 
+/// Shows modifiers held while scrolling in the label (`@Scroll[Ctrl+Down 300px]`).
 #[command]
-pub fn set_ignore_cursor_events(app: AppHandle, ignore: bool) -> Result<(), String> {
-    let window = app.get_webview_window("main").ok_or("No main window found")?;
-    window.set_ignore_cursor_events(ignore).map_err(|e| e.to_string())
+pub fn set_scroll_modifiers(state: State<'_, Arc<CaptureState>>, enabled: bool) {
+    state.update_settings(|s| s.scroll_modifiers = enabled);
+}
+
+/// Switches to raw passthrough: one uninterpreted event per input, with rdev
+/// key/button identifiers and coordinates, for custom overlays.
+#[command]
+pub fn set_raw_passthrough(state: State<'_, Arc<CaptureState>>, enabled: bool) {
+    state.update_settings(|s| s.raw_passthrough = enabled);
+}
+
+/// Sets the interval for `@Scrolled[N px]` milestones. `0` disables them.
+#[command]
+pub fn set_scroll_milestone(state: State<'_, Arc<CaptureState>>, px: u64) {
+    state.update_settings(|s| s.scroll_milestone_px = px);
+}
+
+/// Replaces the shortcut names, given as (combo, name) pairs like
+/// `("Ctrl+Shift+T", "Reopen Tab")`. The order is kept for `preview_shortcuts`.
+#[command]
+pub fn set_shortcut_labels(
+    state: State<'_, Arc<CaptureState>>,
+    labels: Vec<(String, String)>,
+) -> Result<(), String> {
+    let labels = labels
+        .into_iter()
+        .map(|(combo, name)| Ok((Hotkey::parse(&combo)?, name)))
+        .collect::<Result<Vec<_>, String>>()?;
+    state.update_settings(|s| s.shortcut_labels = labels);
+    Ok(())
+}
+
+/// Emits each configured shortcut (`shortcut_labels`) as a regular `shortcut`
+/// event, labelled in the current modifier style with its name appended
+/// (`@Key[Ctrl+C (Copy)]`), so both the mappings and the overlay styling can
+/// be checked without pressing them. Returns immediately; the sequence runs on
+/// its own thread and is stopped by `cancel_preview_shortcuts` or a new preview.
+#[command]
+pub fn preview_shortcuts(app: AppHandle, state: State<'_, Arc<CaptureState>>) {
+    let state = state.inner().clone();
+    let generation = state.preview_generation.fetch_add(1, Ordering::SeqCst) + 1;
+    let labels = preview_labels(&state.settings());
+
+    thread::spawn(move || {
+        for label in labels {
+            if state.preview_generation.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            emit_event(&app, &state, InputEventPayload::now("shortcut", label));
+            thread::sleep(Duration::from_millis(600));
+        }
+    });
+}
+
+#[command]
+pub fn cancel_preview_shortcuts(state: State<'_, Arc<CaptureState>>) {
+    state.preview_generation.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Snapshot of the shared capture state for bug reports: settings, the armed,
+/// paused, recording and listener flags, held modifier/button names and every
+/// counter. Never typed text, so per-key heatmap counts are left out.
+#[command]
+pub fn dump_debug_state(state: State<'_, Arc<CaptureState>>) -> Value {
+    let mut modifiers: Vec<String> = state
+        .pressed_modifiers
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|key| format!("{:?}", key))
+        .collect();
+    modifiers.sort();
+    let buttons: Vec<String> = state
+        .pressed_buttons
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|btn| button_name(*btn))
+        .collect();
+
+    json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "settings": serde_json::to_value(&*state.settings()).unwrap_or(Value::Null),
+        "armed": state.is_armed(),
+        "paused": state.is_paused(),
+        "recording": state.is_recording(),
+        "replaying": state.replaying.load(Ordering::SeqCst),
+        "event_server": state.event_server.lock().unwrap().as_ref().map(|s| s.addr().to_string()),
+        "listener": {
+            "listener_running": state.listener_running.load(Ordering::SeqCst),
+            "capture_thread_running": state.capture_thread_running.load(Ordering::SeqCst),
+            "capture_stopped": state.capture_stopped.load(Ordering::SeqCst),
+            "last_input_ms": state.last_input_ms.load(Ordering::Relaxed),
+        },
+        "held_modifiers": modifiers,
+        "held_buttons": buttons,
+        "counters": {
+            "events_emitted": state.events_emitted.load(Ordering::Relaxed),
+            "events_dropped": state.events_dropped.load(Ordering::Relaxed),
+            "key_presses": state.key_presses.load(Ordering::Relaxed),
+            "button_presses": state.button_presses.load(Ordering::Relaxed),
+            "scroll_distance_px": state.scroll_distance_px.load(Ordering::Relaxed),
+            "name_conflicts": state.name_conflicts.lock().unwrap().len(),
+            "heatmap_keys": state.key_heatmap.lock().unwrap().len(),
+            "latency_samples": state.emit_latency_us.lock().unwrap().len(),
+        },
+        "last_emit_ms": state.last_emit_ms.load(Ordering::Relaxed),
+        "last_activity_ms": state.last_activity_ms.load(Ordering::Relaxed),
+        "last_error": state.last_error.lock().unwrap().clone(),
+    })
+}
+
+/// Sets the debug log verbosity: "off", "error", "warn", "info", "debug" or "trace".
+#[command]
+pub fn set_log_level(level: String) -> Result<(), String> {
+    let filter = logging::parse_level(&level)?;
+    log::set_max_level(filter);
+    log::info!("Log level set to {}", filter);
+    Ok(())
+}
+
+/// Path of the active debug log file, or `None` if logging failed to start.
+#[command]
+pub fn get_log_path() -> Option<String> {
+    logging::log_path().map(|p| p.to_string_lossy().into_owned())
+}
+
+/// Capture-to-emit latency summary in microseconds.
+#[derive(serde::Serialize)]
+pub struct LatencyStats {
+    pub samples: usize,
+    pub min_us: u64,
+    pub avg_us: u64,
+    pub max_us: u64,
+    pub p95_us: u64,
+}
+
+/// Summarises the latency of recently captured events, measured from the OS
+/// event timestamp until `app.emit` returns. Webview delivery and rendering
+/// are not included. Fails if nothing has been emitted yet.
+#[command]
+pub fn measure_emit_latency(state: State<'_, Arc<CaptureState>>) -> Result<LatencyStats, String> {
+    let mut samples: Vec<u64> = state
+        .emit_latency_us
+        .lock()
+        .unwrap()
+        .iter()
+        .copied()
+        .collect();
+    if samples.is_empty() {
+        return Err("No events captured yet".to_string());
+    }
+    samples.sort_unstable();
+    let count = samples.len();
+    let p95_index = (count * 95).div_ceil(100).saturating_sub(1);
+    Ok(LatencyStats {
+        samples: count,
+        min_us: samples[0],
+        avg_us: samples.iter().sum::<u64>() / count as u64,
+        max_us: samples[count - 1],
+        p95_us: samples[p95_index],
+    })
+}
+
+/// The last `count` emitted events (at most 200), oldest first and labelled
+/// as the overlay shows them, so a reloaded overlay can restore its history.
+#[command]
+pub fn get_recent_events(
+    state: State<'_, Arc<CaptureState>>,
+    count: usize,
+) -> Vec<InputEventPayload> {
+    let settings = state.settings();
+    let events = state.recent_events.lock().unwrap();
+    events
+        .iter()
+        .skip(events.len().saturating_sub(count))
+        .map(|event| {
+            let mut event = event.clone();
+            apply_label_format(&settings, &mut event);
+            event
+        })
+        .collect()
+}
+
+/// Writes a plain-text, timestamped transcript for notes and tutorials
+/// (e.g. `00:01.3  Ctrl+C (Copy)`) of the recording at `recording_path` (see
+/// `start_recording`), or of the recent events when no recording is given.
+#[command]
+pub fn export_transcript(
+    state: State<'_, Arc<CaptureState>>,
+    path: String,
+    recording_path: Option<String>,
+) -> Result<(), String> {
+    // Cursor moves never reach the transcript and would dominate the recording
+    let mut events = Vec::new();
+    match recording_path {
+        Some(recording_path) => recording::read_recording_with(&recording_path, |event| {
+            if event.event_type != "mousemove" {
+                events.push(event);
+            }
+        })?,
+        None => events.extend(
+            state
+                .recent_events
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|event| event.event_type != "mousemove")
+                .cloned(),
+        ),
+    }
+    if events.is_empty() {
+        return Err("No events to export".to_string());
+    }
+    let names = shortcut_names(&state.settings());
+    std::fs::write(&path, render_transcript(&events, &names)).map_err(|e| e.to_string())
+}
+
+/// Writes a cheat sheet of the shortcuts used in the recording at
+/// `recording_path` (see `start_recording`), most used first. A `.html` path
+/// produces HTML, anything else Markdown.
+#[command]
+pub fn export_cheatsheet(
+    state: State<'_, Arc<CaptureState>>,
+    recording_path: String,
+    path: String,
+) -> Result<(), String> {
+    // Only shortcuts are kept, so a long recording stays cheap to aggregate
+    let mut events = Vec::new();
+    recording::read_recording_with(&recording_path, |event| {
+        if event.event_type == "shortcut" {
+            events.push(event);
+        }
+    })?;
+    let names = shortcut_names(&state.settings());
+    let sheet = render_cheatsheet(&events, &names, CheatsheetFormat::for_path(&path))
+        .ok_or_else(|| "No shortcuts to export".to_string())?;
+    std::fs::write(&path, sheet).map_err(|e| e.to_string())
+}
+
+/// Accepts `#rgb` or `#rrggbb`.
+fn is_hex_color(value: &str) -> bool {
+    value
+        .strip_prefix('#')
+        .is_some_and(|hex| matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Sets and persists the color of the recording indicator, e.g. `"#ef4444"`.
+#[command]
+pub fn set_recording_indicator_color(
+    app: AppHandle,
+    state: State<'_, Arc<CaptureState>>,
+    color: String,
+) -> Result<(), String> {
+    if !is_hex_color(&color) {
+        return Err(format!("Invalid color: {}", color));
+    }
+    state.update_settings(|s| s.recording_indicator_color = color);
+    emit_recording_state(&app, &state);
+    config::save(&app, &state)
+}
+
+/// Records every emitted event to `path` as JSON lines until `stop_recording`.
+#[command]
+pub fn start_recording(
+    app: AppHandle,
+    state: State<'_, Arc<CaptureState>>,
+    path: String,
+) -> Result<(), String> {
+    let file = recording::open_recording_file(&path)?;
+    recording::start(&app, &state, file)
+}
+
+/// Plays back a recording made with `start_recording`. `speed` scales the
+/// original pacing (`2.0` plays twice as fast).
+#[command]
+pub fn replay_recording(
+    app: AppHandle,
+    state: State<'_, Arc<CaptureState>>,
+    path: String,
+    speed: f64,
+) -> Result<(), String> {
+    recording::replay(app, state.inner().clone(), &path, speed)
+}
+
+/// Where to reach a running event server.
+#[derive(serde::Serialize)]
+pub struct EventServerInfo {
+    /// Bound address, e.g. `127.0.0.1:7878`.
+    pub addr: String,
+    /// Per-session secret; clients pass it as `?token=` or `Authorization: Bearer`.
+    pub token: String,
+    /// Ready-to-use URL including the token.
+    pub url: String,
+}
+
+/// Starts a WebSocket server on `port` that broadcasts every overlay event as
+/// JSON. Only localhost may connect unless `allow_external` is set, and every
+/// client needs the returned token. Browser pages (which always send an
+/// `Origin`) are refused unless their origin is in `allowed_origins`, e.g.
+/// `http://localhost:3000`.
+#[command]
+pub fn start_event_server(
+    state: State<'_, Arc<CaptureState>>,
+    port: u16,
+    allow_external: Option<bool>,
+    allowed_origins: Option<Vec<String>>,
+) -> Result<EventServerInfo, String> {
+    let mut server = state.event_server.lock().unwrap();
+    if server.is_some() {
+        return Err("The event server is already running".to_string());
+    }
+    let started = EventServer::start(
+        port,
+        allow_external.unwrap_or(false),
+        allowed_origins.unwrap_or_default(),
+    )?;
+    let info = EventServerInfo {
+        addr: started.addr().to_string(),
+        token: started.token().to_string(),
+        url: format!("ws://{}/?token={}", started.addr(), started.token()),
+    };
+    *server = Some(started);
+    Ok(info)
+}
+
+/// Stops the event server and disconnects its clients.
+#[command]
+pub fn stop_event_server(state: State<'_, Arc<CaptureState>>) {
+    state.event_server.lock().unwrap().take();
+}
+
+/// Stops the running recording; also cancels a pending countdown.
+#[command]
+pub fn stop_recording(app: AppHandle, state: State<'_, Arc<CaptureState>>) {
+    state.countdown_generation.fetch_add(1, Ordering::SeqCst);
+    recording::stop(&app, &state);
+}
+
+/// Shows a `caption` countdown ("Recording in 3", "2", "1") one second apart,
+/// then starts recording. Returns immediately; `seconds = 0` starts at once.
+/// `cancel_recording_countdown` (or `stop_recording`) aborts it.
+#[command]
+pub fn start_recording_with_countdown(
+    app: AppHandle,
+    state: State<'_, Arc<CaptureState>>,
+    path: String,
+    seconds: u32,
+) -> Result<(), String> {
+    if state.is_recording() {
+        return Err("A recording is already running".to_string());
+    }
+    let file = recording::open_recording_file(&path)?;
+    if seconds == 0 {
+        return recording::start(&app, &state, file);
+    }
+
+    let state = state.inner().clone();
+    let generation = state.countdown_generation.fetch_add(1, Ordering::SeqCst) + 1;
+    thread::spawn(move || {
+        for remaining in (1..=seconds).rev() {
+            if state.countdown_generation.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            emit_event(
+                &app,
+                &state,
+                InputEventPayload::now("caption", format!("@Caption[Recording in {}]", remaining)),
+            );
+            thread::sleep(Duration::from_secs(1));
+        }
+        if state.countdown_generation.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        if let Err(error) = recording::start(&app, &state, file) {
+            log::error!("Countdown recording failed to start: {}", error);
+            emit_system(&app, &state, "Recording Failed");
+        }
+    });
+    Ok(())
+}
+
+#[command]
+pub fn cancel_recording_countdown(state: State<'_, Arc<CaptureState>>) {
+    state.countdown_generation.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Sets how long without key activity before held modifiers are considered
+/// stuck and cleared. `0` disables it.
+#[command]
+pub fn set_stuck_modifier_timeout(state: State<'_, Arc<CaptureState>>, ms: u64) {
+    state.update_settings(|s| s.stuck_modifier_timeout_ms = ms);
+}
+
+/// Injects a pen reading from an external tablet integration as a `pen`
+/// event. Dropped like captured input while capture is paused or disarmed.
+#[command]
+pub fn emit_pen_event(
+    app: AppHandle,
+    state: State<'_, Arc<CaptureState>>,
+    sample: PenSample,
+) -> Result<(), String> {
+    pen::emit_pen(&app, &state, sample)
+}
+
+/// Shortest gap between two `emit_test_event` calls.
+const TEST_EVENT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Longest label accepted by `emit_test_event`.
+const MAX_TEST_LABEL_CHARS: usize = 256;
+
+/// Emits a made-up `input-event`, e.g. `("shortcut", "@Key[Ctrl+S]")`, so the
+/// overlay can be styled and tested without real input. It bypasses capture
+/// and pause, and is limited to one event every 50ms.
+#[command]
+pub fn emit_test_event(
+    app: AppHandle,
+    state: State<'_, Arc<CaptureState>>,
+    event_type: String,
+    label: String,
+) -> Result<(), String> {
+    if event_type.is_empty() {
+        return Err("Test event type must not be empty".to_string());
+    }
+    if label.chars().count() > MAX_TEST_LABEL_CHARS {
+        return Err(format!(
+            "Test event label must be at most {} characters",
+            MAX_TEST_LABEL_CHARS
+        ));
+    }
+    {
+        let mut last = state.last_test_event.lock().unwrap();
+        let now = Instant::now();
+        if last.is_some_and(|t| now.duration_since(t) < TEST_EVENT_INTERVAL) {
+            return Err("Test events are limited to one every 50ms".to_string());
+        }
+        *last = Some(now);
+    }
+    log::debug!("Test event: {} {}", event_type, label);
+    emit_event(&app, &state, InputEventPayload::now(&event_type, label));
+    Ok(())
+}
+
+/// Limits `@MouseMove` events to one per `ms` (0 disables throttling).
+/// Drag detection still sees every move.
+#[command]
+pub fn set_mousemove_throttle(
+    app: AppHandle,
+    state: State<'_, Arc<CaptureState>>,
+    ms: u64,
+) -> Result<(), String> {
+    state.update_settings(|s| s.mousemove_throttle_ms = ms);
+    config::save(&app, &state)
+}
+
+/// Sets the maximum gap between two clicks that still counts as a double-click (50-2000ms).
+#[command]
+pub fn set_double_click_threshold(
+    app: AppHandle,
+    state: State<'_, Arc<CaptureState>>,
+    ms: u64,
+) -> Result<(), String> {
+    if !(50..=2000).contains(&ms) {
+        return Err(format!(
+            "Double-click threshold must be between 50 and 2000ms: {}",
+            ms
+        ));
+    }
+    state.update_settings(|s| s.double_click_threshold_ms = ms);
+    config::save(&app, &state)
+}
+
+/// When on, a double-click shows only `@DoubleClick` instead of a second `@Click` before it.
+#[command]
+pub fn set_suppress_click_on_doubleclick(state: State<'_, Arc<CaptureState>>, enabled: bool) {
+    state.update_settings(|s| s.suppress_click_on_doubleclick = enabled);
+}
+
+/// Current values behind the settings window's controls, so it can show the
+/// real state instead of assuming defaults.
+#[derive(serde::Serialize)]
+pub struct CaptureStatus {
+    pub paused: bool,
+    pub armed: bool,
+    pub recording: bool,
+    pub replaying: bool,
+    /// Turned off with `stop_capture`.
+    pub stopped: bool,
+    pub double_click_ms: u64,
+    pub drag_px: f64,
+    pub mousemove_throttle_ms: u64,
+    /// Layout id as accepted by `set_keyboard_layout`, `None` for the OS layout.
+    pub layout: Option<&'static str>,
+    /// As accepted by `Hotkey::parse`, e.g. `Ctrl+Alt+KeyP`.
+    pub pause_hotkey: String,
+}
+
+#[command]
+pub fn get_capture_state(state: State<'_, Arc<CaptureState>>) -> CaptureStatus {
+    let settings = state.settings();
+    CaptureStatus {
+        paused: state.is_paused(),
+        armed: state.is_armed(),
+        recording: state.is_recording(),
+        replaying: state.replaying.load(Ordering::SeqCst),
+        stopped: state.capture_stopped.load(Ordering::SeqCst),
+        double_click_ms: settings.double_click_threshold_ms,
+        drag_px: settings.drag_threshold_px,
+        mousemove_throttle_ms: settings.mousemove_throttle_ms,
+        layout: settings.keyboard_layout.map(KeyboardLayout::id),
+        pause_hotkey: settings.pause_hotkey.to_string(),
+    }
+}
+
+/// Modifiers and mouse buttons the capture thread believes are held, by rdev
+/// variant name (e.g. `ControlLeft`, `Left`).
+#[derive(serde::Serialize)]
+pub struct PressedState {
+    pub modifiers: Vec<String>,
+    pub buttons: Vec<String>,
+}
+
+#[command]
+pub fn get_pressed_state(state: State<'_, Arc<CaptureState>>) -> PressedState {
+    let mut modifiers: Vec<String> = state
+        .pressed_modifiers
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|key| format!("{:?}", key))
+        .collect();
+    modifiers.sort();
+    let buttons = state
+        .pressed_buttons
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|btn| button_name(*btn))
+        .collect();
+    PressedState { modifiers, buttons }
+}
+
+/// Debug aid: overwrites the held modifiers and buttons, e.g. to reproduce a
+/// stuck-modifier report. Names use the same format as `get_pressed_state`.
+#[command]
+pub fn set_pressed_state(
+    state: State<'_, Arc<CaptureState>>,
+    modifiers: Vec<String>,
+    buttons: Vec<String>,
+) -> Result<(), String> {
+    let mut keys = HashSet::new();
+    for name in &modifiers {
+        let key = parse_key_name(name)
+            .filter(|key| Modifier::from_key(*key).is_some())
+            .ok_or_else(|| format!("Not a modifier key: {}", name))?;
+        keys.insert(key);
+    }
+    let mut held_buttons = Vec::new();
+    for name in &buttons {
+        let btn = parse_button_name(name).ok_or_else(|| format!("Unknown button: {}", name))?;
+        if !held_buttons.contains(&btn) {
+            held_buttons.push(btn);
+        }
+    }
+    *state.pressed_modifiers.lock().unwrap() = keys;
+    *state.pressed_buttons.lock().unwrap() = held_buttons;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hotkeys_conflict_in_every_direction() {
+        let hotkey = |combo| Hotkey::parse(combo).unwrap();
+        let mut settings = Settings::default();
+        assert_eq!(settings.panic_hotkey, None, "panic hotkey is opt-in");
+        settings.panic_hotkey = Some(hotkey("Ctrl+Alt+Shift+K"));
+        settings.clear_hotkey = Some(hotkey("Ctrl+Alt+C"));
+
+        assert_eq!(
+            hotkey_taken_by(&settings, &hotkey("Alt+Ctrl+P"), "panic"),
+            Some("pause")
+        );
+        assert_eq!(
+            hotkey_taken_by(&settings, &hotkey("Ctrl+Alt+C"), "pause"),
+            Some("clear")
+        );
+        assert_eq!(
+            hotkey_taken_by(&settings, &hotkey("Ctrl+Alt+C"), "panic"),
+            Some("clear")
+        );
+        assert_eq!(
+            hotkey_taken_by(&settings, &hotkey("Ctrl+Alt+Shift+K"), "clear"),
+            Some("panic")
+        );
+        // Re-setting a hotkey to its own combo is fine
+        assert_eq!(
+            hotkey_taken_by(&settings, &hotkey("Ctrl+Alt+P"), "pause"),
+            None
+        );
+        assert_eq!(
+            hotkey_taken_by(&settings, &hotkey("Ctrl+Alt+X"), "clear"),
+            None
+        );
+    }
 }
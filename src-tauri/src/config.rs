@@ -0,0 +1,106 @@
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+use crate::state::{CaptureState, Hotkey, KeyboardLayout, OverlayAnchor, Settings};
+
+const CONFIG_FILE_NAME: &str = "settings.json";
+
+/// The part of `Settings` that survives restarts. Every field is optional so
+/// files written by older versions (or edited by hand) still load.
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct PersistedSettings {
+    recording_indicator_color: Option<String>,
+    overlay_anchor: Option<OverlayAnchor>,
+    keyboard_layout: Option<KeyboardLayout>,
+    double_click_threshold_ms: Option<u64>,
+    drag_threshold_px: Option<f64>,
+    mousemove_throttle_ms: Option<u64>,
+    /// Written like `Ctrl+Alt+KeyP`
+    pause_hotkey: Option<String>,
+}
+
+impl PersistedSettings {
+    fn from_settings(settings: &Settings) -> Self {
+        Self {
+            recording_indicator_color: Some(settings.recording_indicator_color.clone()),
+            overlay_anchor: Some(settings.overlay_anchor),
+            keyboard_layout: settings.keyboard_layout,
+            double_click_threshold_ms: Some(settings.double_click_threshold_ms),
+            drag_threshold_px: Some(settings.drag_threshold_px),
+            mousemove_throttle_ms: Some(settings.mousemove_throttle_ms),
+            pause_hotkey: Some(settings.pause_hotkey.to_string()),
+        }
+    }
+
+    fn apply(self, settings: &mut Settings) {
+        if let Some(color) = self.recording_indicator_color {
+            settings.recording_indicator_color = color;
+        }
+        if let Some(anchor) = self.overlay_anchor {
+            settings.overlay_anchor = anchor;
+        }
+        if self.keyboard_layout.is_some() {
+            settings.keyboard_layout = self.keyboard_layout;
+        }
+        if let Some(ms) = self.double_click_threshold_ms {
+            settings.double_click_threshold_ms = ms;
+        }
+        if let Some(px) = self.drag_threshold_px {
+            settings.drag_threshold_px = px;
+        }
+        if let Some(ms) = self.mousemove_throttle_ms {
+            settings.mousemove_throttle_ms = ms;
+        }
+        if let Some(hotkey) = self.pause_hotkey {
+            match Hotkey::parse(&hotkey) {
+                Ok(hotkey) => settings.pause_hotkey = hotkey,
+                Err(e) => log::warn!("Ignoring persisted pause hotkey: {}", e),
+            }
+        }
+    }
+}
+
+fn config_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(dir.join(CONFIG_FILE_NAME))
+}
+
+/// Loads persisted settings into `state`. A missing file keeps the defaults; an
+/// unreadable or corrupt one is logged and ignored.
+pub fn load(app: &AppHandle, state: &CaptureState) {
+    let path = match config_path(app) {
+        Ok(path) => path,
+        Err(e) => {
+            log::warn!("Cannot resolve config dir, using default settings: {}", e);
+            return;
+        }
+    };
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+        Err(e) => {
+            log::warn!(
+                "Cannot read {}, using default settings: {}",
+                path.display(),
+                e
+            );
+            return;
+        }
+    };
+    match serde_json::from_str::<PersistedSettings>(&contents) {
+        Ok(persisted) => state.update_settings(|s| persisted.apply(s)),
+        Err(e) => log::warn!("Ignoring corrupt {}: {}", path.display(), e),
+    }
+}
+
+/// Writes the persisted part of the current settings.
+pub fn save(app: &AppHandle, state: &CaptureState) -> Result<(), String> {
+    let path = config_path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(&PersistedSettings::from_settings(&state.settings()))
+        .map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| e.to_string())
+}
@@ -0,0 +1,74 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+use crate::input_capture::{emit_event, InputEventPayload};
+use crate::state::CaptureState;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Where the cursor came to rest and since when.
+struct Anchor {
+    x: f64,
+    y: f64,
+    since: Instant,
+    fired: bool,
+}
+
+/// Emits `@Dwell[x, y]` when the cursor stays within `dwell_radius_px` of one
+/// spot for `dwell_ms` without a click. Moving away or clicking starts over.
+pub fn start_dwell_watcher(app: AppHandle, state: Arc<CaptureState>) {
+    thread::spawn(move || {
+        let mut anchor: Option<Anchor> = None;
+        let mut seen_presses = state.button_presses.load(Ordering::Relaxed);
+
+        loop {
+            thread::sleep(POLL_INTERVAL);
+
+            let settings = state.settings();
+            if settings.dwell_ms == 0 {
+                anchor = None;
+                continue;
+            }
+            let Some((x, y)) = *state.last_mouse_pos.lock().unwrap() else {
+                continue;
+            };
+
+            let presses = state.button_presses.load(Ordering::Relaxed);
+            let clicked = presses != seen_presses;
+            seen_presses = presses;
+
+            let moved_away = anchor
+                .as_ref()
+                .is_none_or(|a| (x - a.x).hypot(y - a.y) > settings.dwell_radius_px);
+            if clicked || moved_away {
+                anchor = Some(Anchor {
+                    x,
+                    y,
+                    since: Instant::now(),
+                    fired: false,
+                });
+                continue;
+            }
+
+            let Some(a) = anchor.as_mut() else {
+                continue;
+            };
+            if !a.fired && a.since.elapsed() >= Duration::from_millis(settings.dwell_ms) {
+                a.fired = true;
+                if state.is_armed() && !state.is_paused() {
+                    emit_event(
+                        &app,
+                        &state,
+                        InputEventPayload::now(
+                            "dwell",
+                            format!("@Dwell[{}, {}]", a.x.round(), a.y.round()),
+                        ),
+                    );
+                }
+            }
+        }
+    });
+}
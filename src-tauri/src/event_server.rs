@@ -0,0 +1,315 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tungstenite::http::StatusCode;
+
+use crate::input_capture::InputEventPayload;
+
+/// How often the accept loop checks whether the server was stopped.
+const ACCEPT_POLL: Duration = Duration::from_millis(100);
+
+/// Messages buffered per client. A client that falls further behind misses
+/// events rather than slowing down the overlay.
+const CLIENT_BACKLOG: usize = 256;
+
+/// A handshake or write that takes longer than this means the client is gone.
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+
+type Clients = Arc<Mutex<Vec<SyncSender<Arc<str>>>>>;
+
+/// Who may complete the WebSocket handshake.
+struct Access {
+    /// Secret a client must present as `?token=` or `Authorization: Bearer`.
+    token: String,
+    /// Browser origins (`http://localhost:3000`) allowed to connect. A page's
+    /// `Origin` is always sent by the browser, so without this check any
+    /// website open in the user's browser could read every keystroke.
+    allowed_origins: Vec<String>,
+}
+
+/// Local WebSocket server that mirrors every overlay event as JSON, e.g. for
+/// an OBS browser source. Stops when dropped.
+pub(crate) struct EventServer {
+    addr: SocketAddr,
+    token: String,
+    stop: Arc<AtomicBool>,
+    clients: Clients,
+}
+
+impl EventServer {
+    /// Binds to `port` on localhost, or on every interface when
+    /// `allow_external` is set. Port 0 picks a free port. Clients must present
+    /// the server's `token`; browser clients must also come from one of
+    /// `allowed_origins`.
+    pub(crate) fn start(
+        port: u16,
+        allow_external: bool,
+        allowed_origins: Vec<String>,
+    ) -> Result<Self, String> {
+        let host = if allow_external {
+            Ipv4Addr::UNSPECIFIED
+        } else {
+            Ipv4Addr::LOCALHOST
+        };
+        let listener = TcpListener::bind((host, port))
+            .map_err(|e| format!("Cannot bind port {}: {}", port, e))?;
+        // Non-blocking so the accept loop can notice `stop`
+        listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+        let addr = listener.local_addr().map_err(|e| e.to_string())?;
+
+        let token = session_token();
+        let access = Arc::new(Access {
+            token: token.clone(),
+            allowed_origins: allowed_origins
+                .into_iter()
+                .map(|origin| origin.trim_end_matches('/').to_string())
+                .collect(),
+        });
+        let stop = Arc::new(AtomicBool::new(false));
+        let clients: Clients = Arc::new(Mutex::new(Vec::new()));
+        let (thread_stop, thread_clients) = (stop.clone(), clients.clone());
+        thread::spawn(move || {
+            while !thread_stop.load(Ordering::SeqCst) {
+                match listener.accept() {
+                    Ok((stream, peer)) => {
+                        if !allow_external && !peer.ip().is_loopback() {
+                            log::warn!("Event server: rejected connection from {}", peer);
+                            continue;
+                        }
+                        let access = access.clone();
+                        let clients = thread_clients.clone();
+                        thread::spawn(move || serve_client(stream, peer, &access, &clients));
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(ACCEPT_POLL),
+                    Err(e) => {
+                        log::warn!("Event server: accept failed: {}", e);
+                        thread::sleep(ACCEPT_POLL);
+                    }
+                }
+            }
+            log::info!("Event server on {} stopped", addr);
+        });
+        log::info!("Event server listening on ws://{}", addr);
+        Ok(Self {
+            addr,
+            token,
+            stop,
+            clients,
+        })
+    }
+
+    pub(crate) fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Per-session secret clients connect with, e.g. `ws://127.0.0.1:7878/?token=...`.
+    pub(crate) fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Queues `payload` for every connected client, forgetting clients that
+    /// have disconnected. Never blocks.
+    pub(crate) fn broadcast(&self, payload: &InputEventPayload) {
+        let mut clients = self.clients.lock().unwrap();
+        if clients.is_empty() {
+            return;
+        }
+        let json: Arc<str> = match serde_json::to_string(payload) {
+            Ok(json) => json.into(),
+            Err(e) => {
+                log::warn!("Event server: cannot serialize payload: {}", e);
+                return;
+            }
+        };
+        clients.retain(|client| match client.try_send(json.clone()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                log::trace!(
+                    "Event server: client lagging, dropped {}",
+                    payload.event_type
+                );
+                true
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        });
+    }
+}
+
+impl Drop for EventServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // Dropping the senders ends each client's thread, which closes its socket
+        self.clients.lock().unwrap().clear();
+    }
+}
+
+/// Fresh unguessable token for one server run. `RandomState` is seeded from
+/// the OS's random source, which avoids pulling in a crate for a single secret.
+fn session_token() -> String {
+    (0..2)
+        .map(|_| format!("{:016x}", RandomState::new().build_hasher().finish()))
+        .collect()
+}
+
+/// Why a handshake is refused, or `None` to let it through. Requests without
+/// an `Origin` don't come from a browser page; they still need the token.
+fn refusal(
+    access: &Access,
+    origin: Option<&str>,
+    query: Option<&str>,
+    bearer: Option<&str>,
+) -> Option<(StatusCode, &'static str)> {
+    if let Some(origin) = origin {
+        let origin = origin.trim_end_matches('/');
+        if !access
+            .allowed_origins
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(origin))
+        {
+            return Some((StatusCode::FORBIDDEN, "Origin not allowed"));
+        }
+    }
+    let presented = query
+        .into_iter()
+        .flat_map(|query| query.split('&'))
+        .find_map(|pair| pair.strip_prefix("token="))
+        .or_else(|| bearer.and_then(|value| value.strip_prefix("Bearer ")));
+    match presented {
+        Some(token) if constant_time_eq(token.as_bytes(), access.token.as_bytes()) => None,
+        _ => Some((StatusCode::UNAUTHORIZED, "Missing or wrong token")),
+    }
+}
+
+/// Compares without exiting at the first differing byte, so response timing
+/// doesn't reveal how much of a guessed token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Performs the WebSocket handshake and, once the client is admitted, writes
+/// queued messages until it goes away or the server stops.
+fn serve_client(stream: TcpStream, peer: SocketAddr, access: &Access, clients: &Clients) {
+    // Accepted sockets inherit non-blocking mode on some platforms
+    if let Err(e) = stream
+        .set_nonblocking(false)
+        .and_then(|_| stream.set_read_timeout(Some(IO_TIMEOUT)))
+        .and_then(|_| stream.set_write_timeout(Some(IO_TIMEOUT)))
+    {
+        log::warn!(
+            "Event server: cannot configure connection from {}: {}",
+            peer,
+            e
+        );
+        return;
+    }
+    let check = |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+        let headers = request.headers();
+        let origin = headers.get("Origin").and_then(|v| v.to_str().ok());
+        let bearer = headers.get("Authorization").and_then(|v| v.to_str().ok());
+        match refusal(access, origin, request.uri().query(), bearer) {
+            None => Ok(response),
+            Some((status, reason)) => {
+                log::warn!("Event server: refused {}: {}", peer, reason);
+                let mut refused = ErrorResponse::new(Some(reason.to_string()));
+                *refused.status_mut() = status;
+                Err(refused)
+            }
+        }
+    };
+    let mut socket = match tungstenite::accept_hdr(stream, check) {
+        Ok(socket) => socket,
+        Err(e) => {
+            log::warn!("Event server: handshake with {} failed: {}", peer, e);
+            return;
+        }
+    };
+    // Only admitted clients are queued events
+    let (tx, messages) = mpsc::sync_channel(CLIENT_BACKLOG);
+    clients.lock().unwrap().push(tx);
+    log::info!("Event server: client {} connected", peer);
+    for json in messages {
+        if let Err(e) = socket.send(tungstenite::Message::text(json.as_ref())) {
+            log::info!("Event server: client {} disconnected: {}", peer, e);
+            return;
+        }
+    }
+    let _ = socket.close(None);
+    let _ = socket.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn access() -> Access {
+        Access {
+            token: "secret".to_string(),
+            allowed_origins: vec!["http://localhost:3000".to_string()],
+        }
+    }
+
+    #[test]
+    fn admits_token_from_query_or_bearer_header() {
+        let access = access();
+        assert_eq!(refusal(&access, None, Some("token=secret"), None), None);
+        assert_eq!(refusal(&access, None, Some("a=1&token=secret"), None), None);
+        assert_eq!(refusal(&access, None, None, Some("Bearer secret")), None);
+    }
+
+    #[test]
+    fn refuses_missing_or_wrong_token() {
+        let access = access();
+        let unauthorized = Some((StatusCode::UNAUTHORIZED, "Missing or wrong token"));
+        assert_eq!(refusal(&access, None, None, None), unauthorized);
+        assert_eq!(
+            refusal(&access, None, Some("token=guess"), None),
+            unauthorized
+        );
+        assert_eq!(
+            refusal(&access, None, Some("token=secretx"), None),
+            unauthorized
+        );
+    }
+
+    #[test]
+    fn refuses_browser_origins_not_allowed() {
+        let access = access();
+        let forbidden = Some((StatusCode::FORBIDDEN, "Origin not allowed"));
+        assert_eq!(
+            refusal(
+                &access,
+                Some("https://evil.example"),
+                Some("token=secret"),
+                None
+            ),
+            forbidden
+        );
+        assert_eq!(
+            refusal(&access, Some("null"), Some("token=secret"), None),
+            forbidden
+        );
+        assert_eq!(
+            refusal(
+                &access,
+                Some("http://localhost:3000/"),
+                Some("token=secret"),
+                None
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn session_tokens_differ() {
+        let token = session_token();
+        assert_eq!(token.len(), 32);
+        assert_ne!(token, session_token());
+    }
+}
@@ -0,0 +1,81 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+use crate::commands::toggle_overlay_visibility;
+use crate::input_capture::monitor_rect;
+use crate::state::{CaptureState, Corner, Rect};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// How long the cursor has to rest in the corner before the toggle fires.
+const DWELL_TIME: Duration = Duration::from_millis(400);
+/// Size of the square hit area at each corner.
+const CORNER_SIZE: f64 = 4.0;
+/// Monitors can be added or rearranged while running, so bounds are refetched periodically.
+pub(crate) const MONITOR_REFRESH: Duration = Duration::from_secs(5);
+
+fn in_corner(bounds: &Rect, corner: Corner, x: f64, y: f64) -> bool {
+    let left = x < bounds.x + CORNER_SIZE;
+    let right = x >= bounds.x + bounds.width - CORNER_SIZE;
+    let top = y < bounds.y + CORNER_SIZE;
+    let bottom = y >= bounds.y + bounds.height - CORNER_SIZE;
+    match corner {
+        Corner::TopLeft => top && left,
+        Corner::TopRight => top && right,
+        Corner::BottomLeft => bottom && left,
+        Corner::BottomRight => bottom && right,
+    }
+}
+
+/// Toggles the overlay when the cursor dwells in the configured hot corner of
+/// any monitor. The cursor has to leave the corner before it can fire again.
+pub fn start_hot_corner_watcher(app: AppHandle, state: Arc<CaptureState>) {
+    thread::spawn(move || {
+        let mut monitors: Vec<Rect> = Vec::new();
+        let mut monitors_fetched: Option<Instant> = None;
+        let mut dwell_start: Option<Instant> = None;
+        let mut fired = false;
+
+        loop {
+            thread::sleep(POLL_INTERVAL);
+
+            let Some(corner) = state.settings().hot_corner else {
+                dwell_start = None;
+                fired = false;
+                continue;
+            };
+            let Some((x, y)) = *state.last_mouse_pos.lock().unwrap() else {
+                continue;
+            };
+
+            if monitors_fetched.is_none_or(|t| t.elapsed() >= MONITOR_REFRESH) {
+                if let Ok(list) = app.available_monitors() {
+                    let scale_override = state.settings().coordinate_scale_override;
+                    monitors = list
+                        .iter()
+                        .map(|m| monitor_rect(m, scale_override))
+                        .collect();
+                }
+                monitors_fetched = Some(Instant::now());
+            }
+
+            let inside = monitors
+                .iter()
+                .any(|bounds| bounds.contains(x, y) && in_corner(bounds, corner, x, y));
+            if !inside {
+                dwell_start = None;
+                fired = false;
+                continue;
+            }
+
+            let since = *dwell_start.get_or_insert_with(Instant::now);
+            if !fired && since.elapsed() >= DWELL_TIME {
+                fired = true;
+                if let Err(error) = toggle_overlay_visibility(&app) {
+                    log::warn!("Hot corner toggle failed: {}", error);
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,36 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::input_capture::{emit_event, get_timestamp, InputEventPayload};
+use crate::state::CaptureState;
+
+/// How often the watcher checks for idleness; shorter timeouts can't be met.
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Emits one `idle` payload once no input event has been emitted for
+/// `idle_timeout_ms`, so the overlay can fade out. The next event starts the
+/// timer over.
+pub fn start_idle_watcher(app: AppHandle, state: Arc<CaptureState>) {
+    thread::spawn(move || {
+        // Activity timestamp the last `idle` was sent for
+        let mut signalled_for = 0;
+        loop {
+            thread::sleep(POLL_INTERVAL);
+
+            let timeout_ms = state.settings().idle_timeout_ms;
+            let last_activity = state.last_activity_ms.load(Ordering::Relaxed);
+            // Nothing to hide before the first event
+            if timeout_ms == 0 || last_activity == 0 || last_activity == signalled_for {
+                continue;
+            }
+            let idle_ms = (get_timestamp() as u64).saturating_sub(last_activity);
+            if idle_ms >= timeout_ms {
+                signalled_for = last_activity;
+                emit_event(&app, &state, InputEventPayload::now("idle", "Idle"));
+            }
+        }
+    });
+}
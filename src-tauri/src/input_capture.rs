@@ -1,18 +1,733 @@
 use rdev::{listen, Button, EventType, Key};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 use std::thread;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager, Monitor, PhysicalPosition};
+use ts_rs::TS;
+
+use crate::commands::{emit_clear, panic_stop};
+use crate::hot_corner::MONITOR_REFRESH;
+use crate::pipeline::{start_pipeline, CapturedEvent, KeyRole, PipelineSender};
+use crate::state::{
+    CaptureState, CoordinateDisplay, Hotkey, KeyRepeatMode, KeyboardLayout, Modifier,
+    NameConflictPolicy, Rect, Settings, TimestampMode,
+};
 
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-#[derive(Clone, serde::Serialize)]
-struct InputEventPayload {
-    event_type: String,
-    label: String,
+/// One overlay event. Recordings store these one per line, so the same type
+/// reads them back for replay. The overlay's TypeScript type is generated
+/// from this struct (`cargo test export_bindings` rewrites
+/// `src/bindings/InputEventPayload.ts`).
+#[derive(Clone, serde::Serialize, serde::Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub(crate) struct InputEventPayload {
+    pub(crate) event_type: String,
+    pub(crate) label: String,
+    #[ts(type = "number")]
+    pub(crate) timestamp: u128,
+    /// Milliseconds since the previously emitted event (opt-in via settings).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional, as = "Option<f64>")]
+    delta_ms: Option<u128>,
+    /// Shared by every update of one continuous gesture; the overlay replaces
+    /// the previous entry with the same id instead of adding a new one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional, as = "Option<f64>")]
+    pub(crate) gesture_id: Option<u64>,
+    /// Keyboard layout in effect when the event was captured, so exported or
+    /// replayed events stay self-describing after a layout switch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub(crate) layout: Option<String>,
+    /// Cursor position in rdev screen coordinates, on mouse payloads.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    screen_x: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    screen_y: Option<f64>,
+    /// Cursor position relative to the focused window's top-left corner, on
+    /// mouse payloads when the window frame is known: reliable on Windows and
+    /// macOS, best effort on X11, never set on Wayland (see `window_tracker`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    window_x: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    window_y: Option<f64>,
+    /// On `drag` payloads: where the button went down, in screen coordinates.
+    /// The end point is `screen_x`/`screen_y`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    drag_start_x: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    drag_start_y: Option<f64>,
+    /// On `pen` payloads: tip pressure from 0.0 to 1.0, and tilt in degrees.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pressure: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    tilt_x: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    tilt_y: Option<f64>,
+    /// On raw passthrough payloads: the rdev key or button identifier
+    /// (`KeyA`, `Left`) and the wheel delta in notches.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    button: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional, as = "Option<f64>")]
+    delta_x: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional, as = "Option<f64>")]
+    delta_y: Option<i64>,
+    /// The rdev key or button behind the event (`KeyA`, `Unknown(135)`), when
+    /// `include_raw` is on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    raw: Option<String>,
+    /// On shortcuts: how long each modifier (by label) had been held, in ms.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional, as = "Option<HashMap<String, f64>>")]
+    pub(crate) modifier_hold_ms: Option<HashMap<String, u64>>,
+    /// How long the overlay should keep this event, from `ttl_by_type`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional, as = "Option<f64>")]
+    ttl_ms: Option<u64>,
+    /// On `stats` payloads: key and mouse-button presses per minute.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional, as = "Option<f64>")]
+    keys_per_minute: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional, as = "Option<f64>")]
+    clicks_per_minute: Option<u64>,
+}
+
+impl InputEventPayload {
+    pub(crate) fn new(event_type: &str, label: impl Into<String>, timestamp: u128) -> Self {
+        Self {
+            event_type: event_type.to_string(),
+            label: label.into(),
+            timestamp,
+            delta_ms: None,
+            gesture_id: None,
+            layout: None,
+            screen_x: None,
+            screen_y: None,
+            window_x: None,
+            window_y: None,
+            drag_start_x: None,
+            drag_start_y: None,
+            pressure: None,
+            tilt_x: None,
+            tilt_y: None,
+            key: None,
+            button: None,
+            delta_x: None,
+            delta_y: None,
+            raw: None,
+            modifier_hold_ms: None,
+            ttl_ms: None,
+            keys_per_minute: None,
+            clicks_per_minute: None,
+        }
+    }
+
+    pub(crate) fn with_stats(mut self, keys_per_minute: u64, clicks_per_minute: u64) -> Self {
+        self.keys_per_minute = Some(keys_per_minute);
+        self.clicks_per_minute = Some(clicks_per_minute);
+        self
+    }
+
+    pub(crate) fn with_gesture(mut self, id: u64) -> Self {
+        self.gesture_id = Some(id);
+        self
+    }
+
+    /// Sets the cursor position, and the window-relative one when the focused
+    /// window's frame is known.
+    pub(crate) fn at_position(mut self, (x, y): (f64, f64), window_frame: Option<Rect>) -> Self {
+        self.screen_x = Some(x);
+        self.screen_y = Some(y);
+        if let Some(frame) = window_frame {
+            self.window_x = Some(x - frame.x);
+            self.window_y = Some(y - frame.y);
+        }
+        self
+    }
+
+    pub(crate) fn with_drag_start(mut self, (x, y): (f64, f64)) -> Self {
+        self.drag_start_x = Some(x);
+        self.drag_start_y = Some(y);
+        self
+    }
+
+    pub(crate) fn with_pen(
+        mut self,
+        pressure: f64,
+        tilt_x: Option<f64>,
+        tilt_y: Option<f64>,
+    ) -> Self {
+        self.pressure = Some(pressure);
+        self.tilt_x = tilt_x;
+        self.tilt_y = tilt_y;
+        self
+    }
+
+    /// Minimal payload for raw passthrough mode: one event per rdev event, with
+    /// identifiers and coordinates but no interpretation.
+    fn raw(event_type: &EventType, timestamp: u128, cursor: Option<(f64, f64)>) -> Self {
+        let mut payload = match *event_type {
+            EventType::KeyPress(key) => {
+                let mut p = Self::new("keydown", format!("@KeyDown[{:?}]", key), timestamp);
+                p.key = Some(format!("{:?}", key));
+                p
+            }
+            EventType::KeyRelease(key) => {
+                let mut p = Self::new("keyup", format!("@KeyUp[{:?}]", key), timestamp);
+                p.key = Some(format!("{:?}", key));
+                p
+            }
+            EventType::ButtonPress(btn) => {
+                let mut p = Self::new(
+                    "mousedown",
+                    format!("@MouseDown[{}]", button_name(btn)),
+                    timestamp,
+                );
+                p.button = Some(button_name(btn));
+                p
+            }
+            EventType::ButtonRelease(btn) => {
+                let mut p = Self::new(
+                    "mouseup",
+                    format!("@MouseUp[{}]", button_name(btn)),
+                    timestamp,
+                );
+                p.button = Some(button_name(btn));
+                p
+            }
+            EventType::MouseMove { x, y } => Self::new(
+                "mousemove",
+                format!("@MouseMove[{:.0}, {:.0}]", x, y),
+                timestamp,
+            ),
+            EventType::Wheel { delta_x, delta_y } => {
+                let mut p = Self::new(
+                    "wheel",
+                    format!("@Wheel[{}, {}]", delta_x, delta_y),
+                    timestamp,
+                );
+                p.delta_x = Some(delta_x);
+                p.delta_y = Some(delta_y);
+                p
+            }
+        };
+        if let Some((x, y)) = cursor {
+            payload.screen_x = Some(x);
+            payload.screen_y = Some(y);
+        }
+        payload
+    }
+
+    /// Builds a payload stamped with the current time, for events that don't
+    /// originate from the capture thread.
+    pub(crate) fn now(event_type: &str, label: impl Into<String>) -> Self {
+        Self::new(event_type, label, get_timestamp())
+    }
+}
+
+/// Single exit point for `input-event` payloads so per-event metadata stays
+/// consistent no matter where the event came from.
+pub(crate) fn emit_event(app: &AppHandle, state: &CaptureState, mut payload: InputEventPayload) {
+    // The action behind a system event (e.g. pause) has already happened; only its feedback is hidden
+    if payload.event_type == "system" && !state.settings().show_system_events {
+        log::info!("System event not shown: {}", payload.label);
+        return;
+    }
+    let settings = state.settings();
+    if !passes_event_filter(&settings, &payload.event_type) {
+        log::trace!("Dropped {} event: filtered by type", payload.event_type);
+        return;
+    }
+    payload.ttl_ms = settings.ttl_by_type.get(&payload.event_type).copied();
+    let previous = state
+        .last_emit_ms
+        .swap(payload.timestamp as u64, Ordering::Relaxed);
+    if settings.include_delta && previous != 0 {
+        payload.delta_ms = Some(payload.timestamp.saturating_sub(previous as u128));
+    }
+    // Feeds the idle watcher, which compares against the wall clock
+    if !matches!(
+        payload.event_type.as_str(),
+        "system" | "stats" | "clear" | "idle"
+    ) {
+        state
+            .last_activity_ms
+            .store(payload.timestamp as u64, Ordering::Relaxed);
+    }
+    // Everything upstream compares epoch times; only what leaves uses the chosen clock
+    payload.timestamp = convert_timestamp(state, settings.timestamp_mode, payload.timestamp);
+    // Neither the activity meter nor the overlay's own signals are input worth keeping around
+    if !matches!(payload.event_type.as_str(), "stats" | "clear" | "idle") {
+        state.record_recent_event(&payload);
+    }
+    if state.is_recording() {
+        if let Some(recorder) = state.recorder.lock().unwrap().as_ref() {
+            let _ = recorder.send(payload.clone());
+        }
+    }
+    // Only the overlay's copy is reformatted; transcripts and recordings keep the canonical label
+    apply_label_format(&settings, &mut payload);
+    if let Some(server) = state.event_server.lock().unwrap().as_ref() {
+        server.broadcast(&payload);
+    }
+    state.events_emitted.fetch_add(1, Ordering::Relaxed);
+    let _ = app.emit("input-event", payload);
+}
+
+/// Whether `event_filter` lets `event_type` through. Applied at emit, so
+/// pipeline and watcher events (text, paste, stats, dwell, ...) are filtered
+/// like captured ones. System events and the overlay's `clear` always pass.
+fn passes_event_filter(settings: &Settings, event_type: &str) -> bool {
+    settings.event_filter.is_empty()
+        || matches!(event_type, "system" | "clear")
+        || settings
+            .event_filter
+            .iter()
+            .any(|allowed| allowed == event_type)
+}
+
+/// Re-expresses an epoch timestamp on `mode`'s clock. The event's age is
+/// measured on the wall clock, which can only be off if it jumps in the
+/// moments between capture and emit.
+fn convert_timestamp(state: &CaptureState, mode: TimestampMode, epoch_ms: u128) -> u128 {
+    let origin = match mode {
+        TimestampMode::Epoch => return epoch_ms,
+        TimestampMode::Monotonic => state.launched_at,
+        TimestampMode::Relative => *state.capture_started_at.lock().unwrap(),
+    };
+    let age = Duration::from_millis(get_timestamp().saturating_sub(epoch_ms) as u64);
+    let happened_at = Instant::now().checked_sub(age).unwrap_or(origin);
+    happened_at.saturating_duration_since(origin).as_millis()
+}
+
+/// Rewrites `payload`'s label with the user's `label_format`, as the overlay
+/// shows it.
+pub(crate) fn apply_label_format(settings: &Settings, payload: &mut InputEventPayload) {
+    if settings.label_format != DEFAULT_LABEL_FORMAT {
+        if let Some(label) = format_label(&settings.label_format, &payload.label) {
+            payload.label = label;
+        }
+    }
+}
+
+/// Template matching the built-in `@Key[Ctrl+S]` labels.
+pub(crate) const DEFAULT_LABEL_FORMAT: &str = "@{type}[{value}]";
+
+/// Rewrites a canonical `@Type[value]` label with `template`, e.g. `{type}:
+/// {value}` gives `Key: Ctrl+S`. `None` for labels not in that form (such as
+/// system messages), which are shown as they are.
+fn format_label(template: &str, label: &str) -> Option<String> {
+    let rest = label.strip_prefix('@')?;
+    let (event_type, value) = rest.strip_suffix(']')?.split_once('[')?;
+    Some(
+        template
+            .replace("{type}", event_type)
+            .replace("{value}", value),
+    )
+}
+
+/// Emits a `system` event (e.g. capture state changes) to the overlay.
+pub(crate) fn emit_system(app: &AppHandle, state: &CaptureState, label: &str) {
+    emit_event(app, state, InputEventPayload::now("system", label));
+}
+
+/// Toggles the user pause and reports it as a `system` event.
+fn toggle_pause_by_hotkey(
+    state: &CaptureState,
+    pipeline: &PipelineSender,
     timestamp: u128,
+    captured_at: Instant,
+) {
+    let is_paused = state.toggle_paused();
+    log::info!("Capture pause toggled by hotkey: paused={}", is_paused);
+    if state.is_armed() {
+        let status_label = if is_paused { "Paused" } else { "Resumed" };
+        pipeline.send(CapturedEvent {
+            payload: InputEventPayload::new(
+                "system",
+                format!("Capture {}", status_label),
+                timestamp,
+            ),
+            captured_at,
+            role: KeyRole::Other,
+        });
+    }
+}
+
+/// rdev reports wheel deltas in notches/lines; this approximates the pixels a
+/// typical browser scrolls per notch.
+const PIXELS_PER_SCROLL_NOTCH: u64 = 100;
+
+/// A wheel gesture in progress. Inertial scrolling keeps extending it until
+/// the gap between wheel events exceeds `scroll_gesture_gap_ms`.
+struct ScrollGesture {
+    id: u64,
+    direction: &'static str,
+    /// Modifiers held during the gesture, e.g. `Ctrl+`; empty when none.
+    modifiers: String,
+    last_at: Instant,
+    distance_px: u64,
+}
+
+/// Direction of the dominant scroll axis, or `None` for an empty delta.
+fn scroll_direction(delta_x: i64, delta_y: i64) -> Option<&'static str> {
+    if delta_x == 0 && delta_y == 0 {
+        None
+    } else if delta_y.unsigned_abs() >= delta_x.unsigned_abs() {
+        Some(if delta_y > 0 { "Up" } else { "Down" })
+    } else {
+        Some(if delta_x > 0 { "Right" } else { "Left" })
+    }
+}
+
+/// Held modifiers as a `Ctrl+Shift+` label prefix in `modifier_order`,
+/// counting left and right variants once. Empty when no modifier is held.
+fn modifier_prefix(pressed: &HashSet<Key>, settings: &Settings) -> String {
+    let held: Vec<Modifier> = pressed
+        .iter()
+        .filter_map(|k| Modifier::from_key(*k))
+        .collect();
+    settings
+        .modifier_order
+        .iter()
+        .filter(|m| held.contains(m))
+        .map(|m| format!("{}+", modifier_label(pressed, *m, settings)))
+        .collect()
+}
+
+/// `Ctrl+C`-style label for `hotkey`, with its modifiers in `modifier_order`
+/// and `modifier_style` as a live press would show them.
+pub(crate) fn hotkey_label(hotkey: &Hotkey, settings: &Settings) -> String {
+    let mut parts: Vec<String> = settings
+        .modifier_order
+        .iter()
+        .filter(|m| hotkey.modifiers.contains(m))
+        .map(|m| m.styled_label(settings.modifier_style).to_string())
+        .collect();
+    parts.push(key_display_name(hotkey.key));
+    parts.join("+")
+}
+
+/// Label for a held modifier in the chosen `modifier_style`, prefixed `L`/`R`
+/// when distinguishing sides and only one side is down.
+fn modifier_label(pressed: &HashSet<Key>, modifier: Modifier, settings: &Settings) -> String {
+    let name = modifier.styled_label(settings.modifier_style);
+    if !settings.distinguish_modifier_side {
+        return name.to_string();
+    }
+    let (left, right) = modifier.sides();
+    match (pressed.contains(&left), pressed.contains(&right)) {
+        (true, false) => format!("L{}", name),
+        (false, true) => format!("R{}", name),
+        _ => name.to_string(),
+    }
 }
 
-fn get_timestamp() -> u128 {
+/// Every `event_type` an `input-event` payload can carry.
+pub(crate) const EVENT_TYPES: &[&str] = &[
+    "mousemove",
+    "mousedown",
+    "mouseup",
+    "click",
+    "doubleclick",
+    "tripleclick",
+    "mousechord",
+    "dragstart",
+    "drag",
+    "scroll",
+    "scrolled",
+    "wheel",
+    "dwell",
+    "pen",
+    "key",
+    "shortcut",
+    "keydown",
+    "keyup",
+    "keyhold",
+    "paste",
+    "text",
+    "caption",
+    "system",
+    "stats",
+    "idle",
+    "clear",
+];
+
+/// Event types that describe the pointer and get cursor coordinates attached.
+const MOUSE_EVENT_TYPES: &[&str] = &[
+    "mousemove",
+    "mousedown",
+    "mouseup",
+    "click",
+    "doubleclick",
+    "tripleclick",
+    "mousechord",
+    "dragstart",
+    "drag",
+    "scroll",
+];
+
+fn is_mouse_event(event_type: &str) -> bool {
+    MOUSE_EVENT_TYPES.contains(&event_type)
+}
+
+/// Whether `event_filter` lets through any payload that carries cursor
+/// coordinates (mouse events and pen samples).
+pub(crate) fn emits_pointer_events(settings: &Settings) -> bool {
+    MOUSE_EVENT_TYPES
+        .iter()
+        .chain(&["pen"])
+        .any(|event_type| passes_event_filter(settings, event_type))
+}
+
+/// Whether the event was synthesized from touch input. rdev 0.5 reports no
+/// pointer source on any platform (the Windows hook's extra-info signature
+/// isn't exposed), so captured input is always treated as mouse for now.
+fn is_touch_event(_event: &rdev::Event) -> bool {
+    false
+}
+
+fn drag_threshold_px(settings: &Settings, is_touch: bool) -> f64 {
+    match settings.touch_drag_threshold_px {
+        Some(px) if is_touch => px,
+        _ => settings.drag_threshold_px,
+    }
+}
+
+/// Adds `btn` to the held buttons (in press order). Returns the chord it
+/// forms with buttons already held, e.g. `Left+Right`.
+fn press_button(pressed_buttons: &mut Vec<Button>, btn: Button) -> Option<String> {
+    if !pressed_buttons.contains(&btn) {
+        pressed_buttons.push(btn);
+    }
+    (pressed_buttons.len() > 1).then(|| {
+        let buttons: Vec<String> = pressed_buttons.iter().map(|b| button_name(*b)).collect();
+        buttons.join("+")
+    })
+}
+
+/// Removes `btn` from the held buttons; returns whether others are still held.
+fn release_button(pressed_buttons: &mut Vec<Button>, btn: Button) -> bool {
+    pressed_buttons.retain(|b| *b != btn);
+    !pressed_buttons.is_empty()
+}
+
+/// Position of a release of `btn` at `now` in a run of consecutive clicks:
+/// 1 unless it continues `last_click` (same button, within `threshold`).
+fn click_count(
+    last_click: Option<(Button, Instant, u32)>,
+    btn: Button,
+    now: Instant,
+    threshold: Duration,
+) -> u32 {
+    match last_click {
+        Some((last_btn, last_time, count))
+            if last_btn == btn && now.duration_since(last_time) < threshold =>
+        {
+            count + 1
+        }
+        _ => 1,
+    }
+}
+
+/// Payloads for the `click_count`-th click of a run on `clicked` (e.g.
+/// `Ctrl+Left`): the click itself, unless suppressed as part of a multi-click,
+/// then `@DoubleClick`/`@TripleClick` when the run reaches two or three.
+fn click_payloads(
+    click_count: u32,
+    suppress_click_on_doubleclick: bool,
+    clicked: &str,
+    timestamp: u128,
+) -> Vec<InputEventPayload> {
+    let mut payloads = Vec::new();
+    if !(click_count > 1 && suppress_click_on_doubleclick) {
+        payloads.push(InputEventPayload::new(
+            "click",
+            format!("@Click[{}]", clicked),
+            timestamp,
+        ));
+    }
+    let multi = match click_count {
+        2 => Some(("doubleclick", "DoubleClick")),
+        3 => Some(("tripleclick", "TripleClick")),
+        _ => None,
+    };
+    if let Some((event_type, name)) = multi {
+        payloads.push(InputEventPayload::new(
+            event_type,
+            format!("@{}[{}]", name, clicked),
+            timestamp,
+        ));
+    }
+    payloads
+}
+
+/// Whether a held button has moved far enough from `start` to be a drag:
+/// either the cursor is now more than `threshold` away, or it has travelled
+/// more than that in total (`path_px`), e.g. a wiggle ending near the start.
+fn drag_threshold_crossed(
+    start: (f64, f64),
+    pos: (f64, f64),
+    path_px: f64,
+    threshold: f64,
+) -> bool {
+    let dist = ((pos.0 - start.0).powi(2) + (pos.1 - start.1).powi(2)).sqrt();
+    dist > threshold || path_px > threshold
+}
+
+/// Minimum time between automatic click-through toggles, so hovering along a
+/// region edge doesn't thrash the window.
+const CLICK_THROUGH_DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Mouse region of interest: drops pointer events while the pointer at `pos`
+/// is outside `mouse_roi`. Moves always go; clicks, drags and scrolls only
+/// when `roi_filters_clicks` is set. Key events are never affected.
+fn retain_in_roi(
+    settings: &Settings,
+    pos: Option<(f64, f64)>,
+    payloads: &mut Vec<InputEventPayload>,
+) {
+    let (Some(roi), Some((x, y))) = (settings.mouse_roi, pos) else {
+        return;
+    };
+    if roi.contains(x, y) {
+        return;
+    }
+    payloads.retain(|p| match p.event_type.as_str() {
+        "mousemove" => false,
+        "mousedown" | "mouseup" | "click" | "doubleclick" | "tripleclick" | "mousechord"
+        | "dragstart" | "drag" | "scroll" => !settings.roi_filters_clicks,
+        _ => true,
+    });
+}
+
+/// Converts a rect in webview (CSS pixel) coordinates into the screen space rdev
+/// reports cursor positions in: logical points on macOS, physical pixels elsewhere.
+pub(crate) fn window_rect_to_screen(
+    rect: Rect,
+    origin: PhysicalPosition<i32>,
+    scale_factor: f64,
+) -> Rect {
+    if cfg!(target_os = "macos") {
+        Rect {
+            x: origin.x as f64 / scale_factor + rect.x,
+            y: origin.y as f64 / scale_factor + rect.y,
+            ..rect
+        }
+    } else {
+        Rect {
+            x: origin.x as f64 + rect.x * scale_factor,
+            y: origin.y as f64 + rect.y * scale_factor,
+            width: rect.width * scale_factor,
+            height: rect.height * scale_factor,
+        }
+    }
+}
+
+/// Bounds of a monitor in rdev screen coordinates (see `window_rect_to_screen`).
+/// `scale_override` replaces the monitor's reported scale factor when set.
+pub(crate) fn monitor_rect(monitor: &Monitor, scale_override: Option<f64>) -> Rect {
+    let position = monitor.position();
+    let size = monitor.size();
+    let scale = if cfg!(target_os = "macos") {
+        scale_override.unwrap_or_else(|| monitor.scale_factor())
+    } else {
+        1.0
+    };
+    Rect {
+        x: position.x as f64 / scale,
+        y: position.y as f64 / scale,
+        width: size.width as f64 / scale,
+        height: size.height as f64 / scale,
+    }
+}
+
+/// Position of a point within `bounds` as percentages of its width and height.
+fn percent_within(bounds: &Rect, x: f64, y: f64) -> (f64, f64) {
+    (
+        (x - bounds.x) / bounds.width * 100.0,
+        (y - bounds.y) / bounds.height * 100.0,
+    )
+}
+
+/// `@MouseMove[...]` label in the configured coordinate display. Per-monitor
+/// and percent positions are relative to the monitor under the cursor, named
+/// by its 1-based index (`mon:2`; for percentages only with several monitors);
+/// global pixels are used if no monitor contains the point.
+/// `decimals` applies to every mode; `grid_px` snaps pixel positions (after
+/// making them monitor-relative) and is ignored for percentages.
+fn mousemove_label(
+    display: CoordinateDisplay,
+    monitors: &[Rect],
+    x: f64,
+    y: f64,
+    decimals: usize,
+    grid_px: u32,
+) -> String {
+    let monitor = monitors.iter().enumerate().find(|(_, m)| m.contains(x, y));
+    match (display, monitor) {
+        (CoordinateDisplay::Percent, Some((index, bounds))) => {
+            let (px, py) = percent_within(bounds, x, y);
+            if monitors.len() > 1 {
+                format!(
+                    "@MouseMove[mon:{} {:.*}%, {:.*}%]",
+                    index + 1,
+                    decimals,
+                    px,
+                    decimals,
+                    py
+                )
+            } else {
+                format!("@MouseMove[{:.*}%, {:.*}%]", decimals, px, decimals, py)
+            }
+        }
+        (CoordinateDisplay::PerMonitor, Some((index, bounds))) => format!(
+            "@MouseMove[mon:{} {:.*}, {:.*}]",
+            index + 1,
+            decimals,
+            snap_to_grid(x - bounds.x, grid_px),
+            decimals,
+            snap_to_grid(y - bounds.y, grid_px)
+        ),
+        _ => format!(
+            "@MouseMove[{:.*}, {:.*}]",
+            decimals,
+            snap_to_grid(x, grid_px),
+            decimals,
+            snap_to_grid(y, grid_px)
+        ),
+    }
+}
+
+/// Nearest multiple of `grid_px`; 0 or 1 leaves the value as it is.
+fn snap_to_grid(value: f64, grid_px: u32) -> f64 {
+    if grid_px <= 1 {
+        return value;
+    }
+    let grid = f64::from(grid_px);
+    (value / grid).round() * grid
+}
+
+pub(crate) fn get_timestamp() -> u128 {
     let start = SystemTime::now();
     start
         .duration_since(UNIX_EPOCH)
@@ -20,132 +735,1438 @@ fn get_timestamp() -> u128 {
         .as_millis()
 }
 
-pub fn start_capture(app: AppHandle) {
+/// Raw code of the context-menu (Application) key. rdev 0.5 has no variant for
+/// it, so it arrives as `Key::Unknown` with the platform's native code.
+#[cfg(target_os = "windows")]
+const MENU_KEY_CODE: u32 = 0x5D; // VK_APPS
+#[cfg(target_os = "macos")]
+const MENU_KEY_CODE: u32 = 0x6E; // kVK_ContextualMenu
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const MENU_KEY_CODE: u32 = 135; // X11 keycode for KEY_COMPOSE
+
+/// Media and system keys, which rdev 0.5 only reports as `Key::Unknown` with
+/// the platform's native code. macOS delivers play/pause and brightness as
+/// system events rather than key presses, so only the volume keys appear.
+#[cfg(target_os = "windows")]
+const MEDIA_KEY_CODES: &[(u32, &str)] = &[
+    (0xAD, "Mute"),           // VK_VOLUME_MUTE
+    (0xAE, "VolumeDown"),     // VK_VOLUME_DOWN
+    (0xAF, "VolumeUp"),       // VK_VOLUME_UP
+    (0xB0, "NextTrack"),      // VK_MEDIA_NEXT_TRACK
+    (0xB1, "PrevTrack"),      // VK_MEDIA_PREV_TRACK
+    (0xB2, "MediaStop"),      // VK_MEDIA_STOP
+    (0xB3, "PlayPause"),      // VK_MEDIA_PLAY_PAUSE
+    (0xA6, "BrowserBack"),    // VK_BROWSER_BACK
+    (0xA7, "BrowserForward"), // VK_BROWSER_FORWARD
+];
+#[cfg(target_os = "macos")]
+const MEDIA_KEY_CODES: &[(u32, &str)] = &[
+    (0x48, "VolumeUp"),   // kVK_VolumeUp
+    (0x49, "VolumeDown"), // kVK_VolumeDown
+    (0x4A, "Mute"),       // kVK_Mute
+];
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const MEDIA_KEY_CODES: &[(u32, &str)] = &[
+    // X11 keycodes (evdev code + 8)
+    (121, "Mute"),
+    (122, "VolumeDown"),
+    (123, "VolumeUp"),
+    (171, "NextTrack"),
+    (172, "PlayPause"),
+    (173, "PrevTrack"),
+    (174, "MediaStop"),
+    (232, "BrightnessDown"),
+    (233, "BrightnessUp"),
+];
+
+fn media_key_name(code: u32) -> Option<&'static str> {
+    MEDIA_KEY_CODES
+        .iter()
+        .find(|(media_code, _)| *media_code == code)
+        .map(|(_, name)| *name)
+}
+
+/// Fixed, layout-independent key names used for shortcuts and as the last resort
+/// for typed keys.
+fn get_default_key_name(k: Key) -> &'static str {
+    match k {
+        Key::KeyA => "A",
+        Key::KeyB => "B",
+        Key::KeyC => "C",
+        Key::KeyD => "D",
+        Key::KeyE => "E",
+        Key::KeyF => "F",
+        Key::KeyG => "G",
+        Key::KeyH => "H",
+        Key::KeyI => "I",
+        Key::KeyJ => "J",
+        Key::KeyK => "K",
+        Key::KeyL => "L",
+        Key::KeyM => "M",
+        Key::KeyN => "N",
+        Key::KeyO => "O",
+        Key::KeyP => "P",
+        Key::KeyQ => "Q",
+        Key::KeyR => "R",
+        Key::KeyS => "S",
+        Key::KeyT => "T",
+        Key::KeyU => "U",
+        Key::KeyV => "V",
+        Key::KeyW => "W",
+        Key::KeyX => "X",
+        Key::KeyY => "Y",
+        Key::KeyZ => "Z",
+        Key::Num1 => "1",
+        Key::Num2 => "2",
+        Key::Num3 => "3",
+        Key::Num4 => "4",
+        Key::Num5 => "5",
+        Key::Num6 => "6",
+        Key::Num7 => "7",
+        Key::Num8 => "8",
+        Key::Num9 => "9",
+        Key::Num0 => "0",
+        Key::Space => "Space",
+        Key::Return => "Enter",
+        Key::Backspace => "Backspace",
+        Key::Tab => "Tab",
+        Key::Escape => "Esc",
+        Key::UpArrow => "Up",
+        Key::DownArrow => "Down",
+        Key::LeftArrow => "Left",
+        Key::RightArrow => "Right",
+        Key::Home => "Home",
+        Key::End => "End",
+        Key::PageUp => "PgUp",
+        Key::PageDown => "PgDn",
+        Key::Insert => "Ins",
+        Key::Delete => "Del",
+        Key::Minus => "-",
+        Key::Equal => "=",
+        Key::LeftBracket => "[",
+        Key::RightBracket => "]",
+        Key::BackSlash => "\\",
+        Key::SemiColon => ";",
+        Key::Quote => "'",
+        Key::BackQuote => "`",
+        Key::Comma => ",",
+        Key::Dot => ".",
+        Key::Slash => "/",
+        Key::F1 => "F1",
+        Key::F2 => "F2",
+        Key::F3 => "F3",
+        Key::F4 => "F4",
+        Key::F5 => "F5",
+        Key::F6 => "F6",
+        Key::F7 => "F7",
+        Key::F8 => "F8",
+        Key::F9 => "F9",
+        Key::F10 => "F10",
+        Key::F11 => "F11",
+        Key::F12 => "F12",
+        // Numpad keys are prefixed so they stay distinct from the top row
+        Key::Kp0 => "Num0",
+        Key::Kp1 => "Num1",
+        Key::Kp2 => "Num2",
+        Key::Kp3 => "Num3",
+        Key::Kp4 => "Num4",
+        Key::Kp5 => "Num5",
+        Key::Kp6 => "Num6",
+        Key::Kp7 => "Num7",
+        Key::Kp8 => "Num8",
+        Key::Kp9 => "Num9",
+        Key::KpPlus => "Num+",
+        Key::KpMinus => "Num-",
+        Key::KpMultiply => "Num*",
+        Key::KpDivide => "Num/",
+        Key::KpReturn => "NumEnter",
+        Key::KpDelete => "Num.",
+        Key::NumLock => "NumLock",
+        Key::ControlLeft | Key::ControlRight => "Ctrl",
+        Key::ShiftLeft | Key::ShiftRight => "Shift",
+        Key::Alt => "Alt",
+        Key::MetaLeft | Key::MetaRight => "Meta",
+        Key::Unknown(MENU_KEY_CODE) => "Menu",
+        Key::Unknown(code) => media_key_name(code).unwrap_or("?"),
+        _ => "?",
+    }
+}
+
+/// Numpad keys. With NumLock off, Windows reports the navigation key instead
+/// (e.g. `UpArrow` for Num8); rdev doesn't expose the NumLock state on other
+/// platforms, so there they always show as numpad keys.
+fn is_numpad_key(key: Key) -> bool {
+    matches!(
+        key,
+        Key::Kp0
+            | Key::Kp1
+            | Key::Kp2
+            | Key::Kp3
+            | Key::Kp4
+            | Key::Kp5
+            | Key::Kp6
+            | Key::Kp7
+            | Key::Kp8
+            | Key::Kp9
+            | Key::KpPlus
+            | Key::KpMinus
+            | Key::KpMultiply
+            | Key::KpDivide
+            | Key::KpReturn
+            | Key::KpDelete
+    )
+}
+
+/// Accents a dead key holds back for the next key press.
+const DEAD_KEY_MARKS: &[&str] = &["^", "`", "´", "¨", "~"];
+
+/// The accent `key` holds back, if it's a dead key on the active layout.
+/// Layouts without dead keys type these marks directly (`^` is Shift+6 on US
+/// QWERTY), so only the layout's own dead keys count.
+fn dead_key_mark(key: Key, shift: bool, settings: &Settings) -> Option<String> {
+    let layout = settings.keyboard_layout.unwrap_or(KeyboardLayout::Jis);
+    if !layout.has_dead_keys() {
+        return None;
+    }
+    layout_char(layout, key, shift)
+        .map(|(text, _)| text)
+        .filter(|text| DEAD_KEY_MARKS.contains(&text.as_str()))
+}
+
+/// What a held-back dead key `mark` types together with the next key's label:
+/// the accented letter (`^` then `e` is `ê`), the mark alone after Space, or
+/// both characters when the pair doesn't compose. Named keys like Enter drop
+/// the mark.
+fn compose_dead_key(mark: &str, next: &str) -> String {
+    const COMPOSED: &[(&str, &str, &str)] = &[
+        ("^", "aeiouAEIOU", "âêîôûÂÊÎÔÛ"),
+        ("¨", "aeiouyAEIOU", "äëïöüÿÄËÏÖÜ"),
+        ("`", "aeiouAEIOU", "àèìòùÀÈÌÒÙ"),
+        ("´", "aeiouyAEIOUY", "áéíóúýÁÉÍÓÚÝ"),
+        ("~", "anoANO", "ãñõÃÑÕ"),
+    ];
+    if next == "Space" {
+        return mark.to_string();
+    }
+    let mut chars = next.chars();
+    let (Some(base), None) = (chars.next(), chars.next()) else {
+        return next.to_string();
+    };
+    COMPOSED
+        .iter()
+        .filter(|(accent, _, _)| *accent == mark)
+        .find_map(|(_, bases, composed)| {
+            let index = bases.chars().position(|c| c == base)?;
+            composed.chars().nth(index)
+        })
+        .map_or_else(|| format!("{mark}{next}"), String::from)
+}
+
+/// Whether `event_type` is a press or release of one of the `muted_keys`.
+fn is_muted(settings: &Settings, event_type: &EventType) -> bool {
+    matches!(
+        event_type,
+        EventType::KeyPress(key) | EventType::KeyRelease(key) if settings.muted_keys.contains(key)
+    )
+}
+
+/// Notes a press of `key` at `at`, keeping the first press time while the OS
+/// auto-repeats it. Returns whether this press is such a repeat.
+fn record_key_press(press_times: &mut HashMap<Key, Instant>, key: Key, at: Instant) -> bool {
+    let is_repeat = press_times.contains_key(&key);
+    press_times.entry(key).or_insert(at);
+    is_repeat
+}
+
+/// How long `key` was held when released at `at`, forgetting its press.
+/// `None` if its press wasn't seen.
+fn key_held_for(
+    press_times: &mut HashMap<Key, Instant>,
+    key: Key,
+    at: Instant,
+) -> Option<Duration> {
+    press_times
+        .remove(&key)
+        .map(|pressed_at| at.saturating_duration_since(pressed_at))
+}
+
+/// A modifier release rdev missed (e.g. focus lost mid-combo) would otherwise
+/// stick forever, so a key event arriving `timeout_ms` or more after the last
+/// one clears every held modifier. Returns whether anything was cleared.
+fn clear_stuck_modifiers(
+    pressed_modifiers: &mut HashSet<Key>,
+    press_times: &mut HashMap<Key, Instant>,
+    last_key_activity: Option<Instant>,
+    now: Instant,
+    timeout_ms: u64,
+) -> bool {
+    let timed_out = timeout_ms > 0
+        && last_key_activity
+            .is_some_and(|t| now.saturating_duration_since(t) >= Duration::from_millis(timeout_ms));
+    if !timed_out || pressed_modifiers.is_empty() {
+        return false;
+    }
+    log::warn!("Clearing stuck modifiers: {:?}", pressed_modifiers);
+    pressed_modifiers.clear();
+    press_times.retain(|k, _| Modifier::from_key(*k).is_none());
+    true
+}
+
+/// How long each held modifier had been down at `now`, keyed by its label.
+/// Left and right variants share a label, so the longer hold wins.
+fn modifier_hold_ms(press_times: &HashMap<Key, Instant>, now: Instant) -> HashMap<String, u64> {
+    let mut hold_ms = HashMap::new();
+    for (&held_key, &pressed_at) in press_times {
+        if let Some(modifier) = Modifier::from_key(held_key) {
+            let ms = now.saturating_duration_since(pressed_at).as_millis() as u64;
+            let entry = hold_ms.entry(modifier.label().to_string()).or_insert(0);
+            *entry = (*entry).max(ms);
+        }
+    }
+    hold_ms
+}
+
+/// `@KeyUp[A 420ms]` label for a key held `held_for`.
+fn keyup_label(key_name: &str, held_for: Duration) -> String {
+    format!("@KeyUp[{} {}ms]", key_name, held_for.as_millis())
+}
+
+/// Name of a released key for `@KeyUp`/`@KeyHold`, masked like typed
+/// characters while privacy mode is on.
+fn released_key_name(settings: &Settings, key: Key) -> String {
+    let name = key_display_name(key);
+    let is_character = name.chars().count() == 1 || (is_numpad_key(key) && key != Key::KpReturn);
+    if settings.privacy_mode && is_character {
+        settings.privacy_mask.clone()
+    } else {
+        name
+    }
+}
+
+/// Every named key (rdev variants plus known raw codes), used to resolve
+/// user-supplied key names.
+const ALL_KEYS: &[Key] = &[
+    Key::Alt,
+    Key::AltGr,
+    Key::Backspace,
+    Key::CapsLock,
+    Key::ControlLeft,
+    Key::ControlRight,
+    Key::Delete,
+    Key::DownArrow,
+    Key::End,
+    Key::Escape,
+    Key::F1,
+    Key::F2,
+    Key::F3,
+    Key::F4,
+    Key::F5,
+    Key::F6,
+    Key::F7,
+    Key::F8,
+    Key::F9,
+    Key::F10,
+    Key::F11,
+    Key::F12,
+    Key::Home,
+    Key::LeftArrow,
+    Key::MetaLeft,
+    Key::MetaRight,
+    Key::PageDown,
+    Key::PageUp,
+    Key::Return,
+    Key::RightArrow,
+    Key::ShiftLeft,
+    Key::ShiftRight,
+    Key::Space,
+    Key::Tab,
+    Key::UpArrow,
+    Key::PrintScreen,
+    Key::ScrollLock,
+    Key::Pause,
+    Key::NumLock,
+    Key::BackQuote,
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+    Key::Num0,
+    Key::Minus,
+    Key::Equal,
+    Key::KeyQ,
+    Key::KeyW,
+    Key::KeyE,
+    Key::KeyR,
+    Key::KeyT,
+    Key::KeyY,
+    Key::KeyU,
+    Key::KeyI,
+    Key::KeyO,
+    Key::KeyP,
+    Key::LeftBracket,
+    Key::RightBracket,
+    Key::KeyA,
+    Key::KeyS,
+    Key::KeyD,
+    Key::KeyF,
+    Key::KeyG,
+    Key::KeyH,
+    Key::KeyJ,
+    Key::KeyK,
+    Key::KeyL,
+    Key::SemiColon,
+    Key::Quote,
+    Key::BackSlash,
+    Key::IntlBackslash,
+    Key::KeyZ,
+    Key::KeyX,
+    Key::KeyC,
+    Key::KeyV,
+    Key::KeyB,
+    Key::KeyN,
+    Key::KeyM,
+    Key::Comma,
+    Key::Dot,
+    Key::Slash,
+    Key::Insert,
+    Key::KpReturn,
+    Key::KpMinus,
+    Key::KpPlus,
+    Key::KpMultiply,
+    Key::KpDivide,
+    Key::Kp0,
+    Key::Kp1,
+    Key::Kp2,
+    Key::Kp3,
+    Key::Kp4,
+    Key::Kp5,
+    Key::Kp6,
+    Key::Kp7,
+    Key::Kp8,
+    Key::Kp9,
+    Key::KpDelete,
+    Key::Function,
+    Key::Unknown(MENU_KEY_CODE),
+];
+
+/// Resolves a user-supplied key name to an rdev key. Accepts the label names
+/// (`A`, `F5`, `Esc`, `Ctrl`, `VolumeUp`), rdev variant names (`KeyA`,
+/// `CapsLock`, `Function`) and raw codes as `Unknown(123)`, all
+/// case-insensitive.
+pub(crate) fn parse_key_name(name: &str) -> Option<Key> {
+    let name = name.trim();
+    if let Some(code) = name
+        .strip_prefix("Unknown(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        return code.parse().ok().map(Key::Unknown);
+    }
+    if let Some((code, _)) = MEDIA_KEY_CODES
+        .iter()
+        .find(|(_, media_name)| media_name.eq_ignore_ascii_case(name))
+    {
+        return Some(Key::Unknown(*code));
+    }
+    ALL_KEYS.iter().copied().find(|&key| {
+        get_default_key_name(key).eq_ignore_ascii_case(name)
+            || format!("{:?}", key).eq_ignore_ascii_case(name)
+    })
+}
+
+/// Codes rdev reports as `Button::Unknown` for the back/forward side buttons:
+/// X11 buttons 8/9, Windows XBUTTON1/2, and the 4/5 used elsewhere.
+#[cfg(target_os = "linux")]
+const SIDE_BUTTON_CODES: (u8, u8) = (8, 9);
+#[cfg(target_os = "windows")]
+const SIDE_BUTTON_CODES: (u8, u8) = (1, 2);
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+const SIDE_BUTTON_CODES: (u8, u8) = (4, 5);
+
+/// Display name for a mouse button: `Left`, `Right`, `Middle`, `Back`,
+/// `Forward`, or `Unknown(n)` for anything else.
+pub(crate) fn button_name(btn: Button) -> String {
+    match btn {
+        Button::Left => "Left".to_string(),
+        Button::Right => "Right".to_string(),
+        Button::Middle => "Middle".to_string(),
+        Button::Unknown(code) if code == SIDE_BUTTON_CODES.0 => "Back".to_string(),
+        Button::Unknown(code) if code == SIDE_BUTTON_CODES.1 => "Forward".to_string(),
+        Button::Unknown(code) => format!("Unknown({})", code),
+    }
+}
+
+/// Resolves a mouse button name as produced by [`button_name`]
+/// (`Left`, `Back`, `Unknown(6)`, ...), case-insensitive.
+pub(crate) fn parse_button_name(name: &str) -> Option<Button> {
+    let name = name.trim();
+    if let Some(code) = name
+        .strip_prefix("Unknown(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        return code.parse().ok().map(Button::Unknown);
+    }
+    [
+        Button::Left,
+        Button::Right,
+        Button::Middle,
+        Button::Unknown(SIDE_BUTTON_CODES.0),
+        Button::Unknown(SIDE_BUTTON_CODES.1),
+    ]
+    .into_iter()
+    .find(|btn| button_name(*btn).eq_ignore_ascii_case(name))
+}
+
+/// Keys that produce the same output on every supported layout: Latin
+/// letters in their US positions and the named editing keys.
+fn common_char(key: Key, shift: bool) -> Option<(String, bool)> {
+    match key {
+        Key::KeyA => Some((if shift { "A" } else { "a" }.to_string(), true)),
+        Key::KeyB => Some((if shift { "B" } else { "b" }.to_string(), true)),
+        Key::KeyC => Some((if shift { "C" } else { "c" }.to_string(), true)),
+        Key::KeyD => Some((if shift { "D" } else { "d" }.to_string(), true)),
+        Key::KeyE => Some((if shift { "E" } else { "e" }.to_string(), true)),
+        Key::KeyF => Some((if shift { "F" } else { "f" }.to_string(), true)),
+        Key::KeyG => Some((if shift { "G" } else { "g" }.to_string(), true)),
+        Key::KeyH => Some((if shift { "H" } else { "h" }.to_string(), true)),
+        Key::KeyI => Some((if shift { "I" } else { "i" }.to_string(), true)),
+        Key::KeyJ => Some((if shift { "J" } else { "j" }.to_string(), true)),
+        Key::KeyK => Some((if shift { "K" } else { "k" }.to_string(), true)),
+        Key::KeyL => Some((if shift { "L" } else { "l" }.to_string(), true)),
+        Key::KeyM => Some((if shift { "M" } else { "m" }.to_string(), true)),
+        Key::KeyN => Some((if shift { "N" } else { "n" }.to_string(), true)),
+        Key::KeyO => Some((if shift { "O" } else { "o" }.to_string(), true)),
+        Key::KeyP => Some((if shift { "P" } else { "p" }.to_string(), true)),
+        Key::KeyQ => Some((if shift { "Q" } else { "q" }.to_string(), true)),
+        Key::KeyR => Some((if shift { "R" } else { "r" }.to_string(), true)),
+        Key::KeyS => Some((if shift { "S" } else { "s" }.to_string(), true)),
+        Key::KeyT => Some((if shift { "T" } else { "t" }.to_string(), true)),
+        Key::KeyU => Some((if shift { "U" } else { "u" }.to_string(), true)),
+        Key::KeyV => Some((if shift { "V" } else { "v" }.to_string(), true)),
+        Key::KeyW => Some((if shift { "W" } else { "w" }.to_string(), true)),
+        Key::KeyX => Some((if shift { "X" } else { "x" }.to_string(), true)),
+        Key::KeyY => Some((if shift { "Y" } else { "y" }.to_string(), true)),
+        Key::KeyZ => Some((if shift { "Z" } else { "z" }.to_string(), true)),
+
+        // Named keys
+        Key::Space => Some(("Space".to_string(), false)),
+        Key::Return => Some(("Enter".to_string(), false)),
+        Key::Backspace => Some(("Backspace".to_string(), false)),
+        Key::Tab => Some(("Tab".to_string(), false)),
+        Key::Escape => Some(("Esc".to_string(), false)),
+
+        _ => None,
+    }
+}
+
+/// Character a key produces on a JIS keyboard, and whether Shift was consumed
+/// producing it (so it isn't shown as a separate modifier). `None` for keys
+/// without a mapping.
+fn get_jis_char(key: Key, shift: bool) -> Option<(String, bool)> {
+    match key {
+        // Common JIS Numbers
+        Key::Num1 => Some((if shift { "!" } else { "1" }.to_string(), true)),
+        Key::Num2 => Some((if shift { "\"" } else { "2" }.to_string(), true)),
+        Key::Num3 => Some((if shift { "#" } else { "3" }.to_string(), true)),
+        Key::Num4 => Some((if shift { "$" } else { "4" }.to_string(), true)),
+        Key::Num5 => Some((if shift { "%" } else { "5" }.to_string(), true)),
+        Key::Num6 => Some((if shift { "&" } else { "6" }.to_string(), true)),
+        Key::Num7 => Some((if shift { "'" } else { "7" }.to_string(), true)),
+        Key::Num8 => Some((if shift { "(" } else { "8" }.to_string(), true)),
+        Key::Num9 => Some((if shift { ")" } else { "9" }.to_string(), true)),
+        Key::Num0 => Some(("0".to_string(), false)),
+
+        // The Ro key left of right Shift (reported as IntlBackslash on Windows)
+        Key::IntlBackslash => Some((if shift { "_" } else { "\\" }.to_string(), true)),
+        Key::Comma => Some((if shift { "<" } else { "," }.to_string(), true)),
+        Key::Dot => Some((if shift { ">" } else { "." }.to_string(), true)),
+        Key::Slash => Some((if shift { "?" } else { "/" }.to_string(), true)),
+        Key::Minus => Some((if shift { "=" } else { "-" }.to_string(), true)),
+
+        _ => jis_symbol_char(key, shift).or_else(|| common_char(key, shift)),
+    }
+}
+
+/// JIS106 symbol keys on Windows. rdev names keys after their virtual-key
+/// code, and the JIS driver assigns the `VK_OEM_*` codes by character rather
+/// than position: `VK_OEM_PLUS` (`Equal`) is the `;` key, `VK_OEM_7` (`Quote`)
+/// the `^` key and `VK_OEM_5` (`BackSlash`) the yen key next to Backspace.
+#[cfg(target_os = "windows")]
+fn jis_symbol_char(key: Key, shift: bool) -> Option<(String, bool)> {
+    match key {
+        Key::BackQuote => Some((if shift { "`" } else { "@" }.to_string(), true)),
+        Key::LeftBracket => Some((if shift { "{" } else { "[" }.to_string(), true)),
+        Key::RightBracket => Some((if shift { "}" } else { "]" }.to_string(), true)),
+        Key::BackSlash => Some((if shift { "|" } else { "¥" }.to_string(), true)),
+        Key::SemiColon => Some((if shift { "*" } else { ":" }.to_string(), true)),
+        Key::Equal => Some((if shift { "+" } else { ";" }.to_string(), true)),
+        Key::Quote => Some((if shift { "~" } else { "^" }.to_string(), true)),
+        _ => None,
+    }
+}
+
+/// JIS106 symbol keys on Linux and macOS, where rdev names keys after their
+/// US QWERTY position: the `=` position holds `^`, `[` holds `@`, and the
+/// `` ` `` position is the Hankaku/Zenkaku key, which types nothing. The yen
+/// key has no rdev variant there.
+#[cfg(not(target_os = "windows"))]
+fn jis_symbol_char(key: Key, shift: bool) -> Option<(String, bool)> {
+    match key {
+        Key::BackQuote => Some(("Hankaku".to_string(), false)),
+        Key::Equal => Some((if shift { "~" } else { "^" }.to_string(), true)),
+        Key::LeftBracket => Some((if shift { "`" } else { "@" }.to_string(), true)),
+        Key::RightBracket => Some((if shift { "{" } else { "[" }.to_string(), true)),
+        Key::BackSlash => Some((if shift { "}" } else { "]" }.to_string(), true)),
+        Key::SemiColon => Some((if shift { "+" } else { ";" }.to_string(), true)),
+        Key::Quote => Some((if shift { "*" } else { ":" }.to_string(), true)),
+        _ => None,
+    }
+}
+
+/// Like `get_jis_char`, for a US QWERTY keyboard.
+fn get_us_char(key: Key, shift: bool) -> Option<(String, bool)> {
+    match key {
+        Key::Num1 => Some((if shift { "!" } else { "1" }.to_string(), true)),
+        Key::Num2 => Some((if shift { "@" } else { "2" }.to_string(), true)),
+        Key::Num3 => Some((if shift { "#" } else { "3" }.to_string(), true)),
+        Key::Num4 => Some((if shift { "$" } else { "4" }.to_string(), true)),
+        Key::Num5 => Some((if shift { "%" } else { "5" }.to_string(), true)),
+        Key::Num6 => Some((if shift { "^" } else { "6" }.to_string(), true)),
+        Key::Num7 => Some((if shift { "&" } else { "7" }.to_string(), true)),
+        Key::Num8 => Some((if shift { "*" } else { "8" }.to_string(), true)),
+        Key::Num9 => Some((if shift { "(" } else { "9" }.to_string(), true)),
+        Key::Num0 => Some((if shift { ")" } else { "0" }.to_string(), true)),
+
+        Key::BackQuote => Some((if shift { "~" } else { "`" }.to_string(), true)),
+        Key::Minus => Some((if shift { "_" } else { "-" }.to_string(), true)),
+        Key::Equal => Some((if shift { "+" } else { "=" }.to_string(), true)),
+        Key::LeftBracket => Some((if shift { "{" } else { "[" }.to_string(), true)),
+        Key::RightBracket => Some((if shift { "}" } else { "]" }.to_string(), true)),
+        Key::BackSlash => Some((if shift { "|" } else { "\\" }.to_string(), true)),
+        Key::SemiColon => Some((if shift { ":" } else { ";" }.to_string(), true)),
+        Key::Quote => Some((if shift { "\"" } else { "'" }.to_string(), true)),
+        Key::Comma => Some((if shift { "<" } else { "," }.to_string(), true)),
+        Key::Dot => Some((if shift { ">" } else { "." }.to_string(), true)),
+        Key::Slash => Some((if shift { "?" } else { "/" }.to_string(), true)),
+
+        _ => common_char(key, shift),
+    }
+}
+
+/// Like `get_jis_char`, for a French AZERTY keyboard. rdev names keys by their
+/// US position, so e.g. `KeyQ` is the AZERTY `A` key. Dead keys (`^`, `¨`)
+/// are shown as the accent itself.
+fn get_azerty_char(key: Key, shift: bool) -> Option<(String, bool)> {
+    match key {
+        Key::KeyQ => Some((if shift { "A" } else { "a" }.to_string(), true)),
+        Key::KeyA => Some((if shift { "Q" } else { "q" }.to_string(), true)),
+        Key::KeyW => Some((if shift { "Z" } else { "z" }.to_string(), true)),
+        Key::KeyZ => Some((if shift { "W" } else { "w" }.to_string(), true)),
+        Key::SemiColon => Some((if shift { "M" } else { "m" }.to_string(), true)),
+
+        // Digits need Shift on AZERTY
+        Key::Num1 => Some((if shift { "1" } else { "&" }.to_string(), true)),
+        Key::Num2 => Some((if shift { "2" } else { "é" }.to_string(), true)),
+        Key::Num3 => Some((if shift { "3" } else { "\"" }.to_string(), true)),
+        Key::Num4 => Some((if shift { "4" } else { "'" }.to_string(), true)),
+        Key::Num5 => Some((if shift { "5" } else { "(" }.to_string(), true)),
+        Key::Num6 => Some((if shift { "6" } else { "-" }.to_string(), true)),
+        Key::Num7 => Some((if shift { "7" } else { "è" }.to_string(), true)),
+        Key::Num8 => Some((if shift { "8" } else { "_" }.to_string(), true)),
+        Key::Num9 => Some((if shift { "9" } else { "ç" }.to_string(), true)),
+        Key::Num0 => Some((if shift { "0" } else { "à" }.to_string(), true)),
+
+        Key::BackQuote => Some(("²".to_string(), false)),
+        Key::Minus => Some((if shift { "°" } else { ")" }.to_string(), true)),
+        Key::Equal => Some((if shift { "+" } else { "=" }.to_string(), true)),
+        Key::LeftBracket => Some((if shift { "¨" } else { "^" }.to_string(), true)),
+        Key::RightBracket => Some((if shift { "£" } else { "$" }.to_string(), true)),
+        Key::BackSlash => Some((if shift { "µ" } else { "*" }.to_string(), true)),
+        Key::Quote => Some((if shift { "%" } else { "ù" }.to_string(), true)),
+        Key::KeyM => Some((if shift { "?" } else { "," }.to_string(), true)),
+        Key::Comma => Some((if shift { "." } else { ";" }.to_string(), true)),
+        Key::Dot => Some((if shift { "/" } else { ":" }.to_string(), true)),
+        Key::Slash => Some((if shift { "§" } else { "!" }.to_string(), true)),
+        Key::IntlBackslash => Some((if shift { ">" } else { "<" }.to_string(), true)),
+
+        _ => common_char(key, shift),
+    }
+}
+
+/// Follows CapsLock from its key events. macOS reports the lock state itself
+/// (a press when it turns on, a release when it turns off); elsewhere each
+/// press toggles it, ignoring auto-repeat while the key is held.
+fn track_caps_lock(caps_lock: &mut bool, event_type: &EventType, is_repeat: bool) {
+    if cfg!(target_os = "macos") {
+        match event_type {
+            EventType::KeyPress(Key::CapsLock) => *caps_lock = true,
+            EventType::KeyRelease(Key::CapsLock) => *caps_lock = false,
+            _ => {}
+        }
+    } else if matches!(event_type, EventType::KeyPress(Key::CapsLock)) && !is_repeat {
+        *caps_lock = !*caps_lock;
+    }
+}
+
+/// Flips the case of a single letter from the fallback tables while CapsLock
+/// is on, so `a` shows as `A` and Shift+`a` as `a`. OS-provided names already
+/// reflect CapsLock.
+fn apply_caps_lock(text: String, caps_lock: bool) -> String {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if caps_lock && c.is_alphabetic() => {
+            if c.is_uppercase() {
+                c.to_lowercase().collect()
+            } else {
+                c.to_uppercase().collect()
+            }
+        }
+        _ => text,
+    }
+}
+
+/// Fallback character for `key` on `layout`, used when the OS name is
+/// unavailable (or disagrees, see `NameConflictPolicy`).
+pub(crate) fn layout_char(layout: KeyboardLayout, key: Key, shift: bool) -> Option<(String, bool)> {
+    match layout {
+        KeyboardLayout::UsQwerty => get_us_char(key, shift),
+        KeyboardLayout::Jis => get_jis_char(key, shift),
+        KeyboardLayout::Azerty => get_azerty_char(key, shift),
+    }
+}
+
+/// The mapping table's text for `key` (with CapsLock applied) and whether it
+/// consumes Shift, when it differs from the OS-provided `os_name`.
+fn table_disagreement(
+    layout: KeyboardLayout,
+    key: Key,
+    shift: bool,
+    caps_lock: bool,
+    os_name: &str,
+) -> Option<(String, bool)> {
+    layout_char(layout, key, shift)
+        .map(|(text, consumed)| (apply_caps_lock(text, caps_lock), consumed))
+        .filter(|(text, _)| text != os_name)
+}
+
+/// Picks the label for a key whose OS name and table text disagree, each
+/// given with whether it consumes Shift. Returns the label, whether it
+/// consumes Shift, and whether it shows both names.
+fn resolve_name_conflict(
+    policy: NameConflictPolicy,
+    os: (String, bool),
+    manual: (String, bool),
+) -> (String, bool, bool) {
+    match policy {
+        NameConflictPolicy::Os => (os.0, os.1, false),
+        NameConflictPolicy::Manual => (manual.0, manual.1, false),
+        NameConflictPolicy::Both => (format!("{}/{}", os.0, manual.0), os.1, true),
+    }
+}
+
+/// Default key name, falling back to the rdev variant name (minus any `Key`
+/// prefix) for keys without an explicit mapping.
+fn key_display_name(key: Key) -> String {
+    match get_default_key_name(key) {
+        "?" => {
+            let s = format!("{:?}", key);
+            if s.starts_with("Key") && s.len() > 3 {
+                s[3..].to_string()
+            } else {
+                s
+            }
+        }
+        name => name.to_string(),
+    }
+}
+
+/// Adds a press of `key` to the heatmap while it is enabled and capture is
+/// armed. Counts physical keys only, so no typed text is retained.
+fn count_heatmap_press(state: &CaptureState, key: Key) {
+    let settings = state.settings();
+    if !state.is_armed() || !settings.key_heatmap || settings.privacy_mode {
+        return;
+    }
+    *state
+        .key_heatmap
+        .lock()
+        .unwrap()
+        .entry(key_display_name(key))
+        .or_insert(0) += 1;
+}
+
+/// Ends a running rdev `listen` from another thread, so a stopped capture
+/// drops its OS hook instead of idling in it. rdev 0.5 has no API for this:
+/// on Windows its hook thread waits in `GetMessage`, which returns on
+/// `WM_QUIT` (the low-level hooks go away when that thread exits), and on
+/// macOS it runs the thread's run loop, which `CFRunLoopStop` ends. X11's
+/// record context can only be disabled through rdev's own display
+/// connection, so there the listener keeps running and ignores input.
+pub struct ListenerExit(
+    // Thread id on Windows, `CFRunLoopRef` on macOS
+    #[cfg_attr(not(any(target_os = "windows", target_os = "macos")), allow(dead_code))] usize,
+);
+
+impl ListenerExit {
+    /// Handle for the calling thread, if the platform can end its listener.
+    #[cfg(target_os = "windows")]
+    fn current() -> Option<Self> {
+        let thread_id = unsafe { windows_sys::Win32::System::Threading::GetCurrentThreadId() };
+        Some(Self(thread_id as usize))
+    }
+
+    #[cfg(target_os = "macos")]
+    fn current() -> Option<Self> {
+        let run_loop = unsafe { core_foundation_sys::runloop::CFRunLoopGetCurrent() };
+        Some(Self(run_loop as usize))
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    fn current() -> Option<Self> {
+        None
+    }
+
+    /// Makes `listen` return on the listener's thread. Only called while the
+    /// thread is still in `listen` (the handle is dropped under the same lock
+    /// once it returns), so the id or run loop is still valid.
+    #[cfg(target_os = "windows")]
+    fn end(&self) -> bool {
+        use windows_sys::Win32::UI::WindowsAndMessaging::{PostThreadMessageW, WM_QUIT};
+        unsafe { PostThreadMessageW(self.0 as u32, WM_QUIT, 0, 0) != 0 }
+    }
+
+    #[cfg(target_os = "macos")]
+    fn end(&self) -> bool {
+        use core_foundation_sys::runloop::{CFRunLoopRef, CFRunLoopStop};
+        unsafe { CFRunLoopStop(self.0 as CFRunLoopRef) };
+        true
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    fn end(&self) -> bool {
+        false
+    }
+}
+
+/// Ends the running listener where the platform allows it (see
+/// `ListenerExit`). Returns whether it will exit; otherwise it stays
+/// installed and drops input while `capture_stopped` is set.
+pub(crate) fn end_listener(state: &CaptureState) -> bool {
+    state
+        .listener_exit
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(ListenerExit::end)
+}
+
+/// Restarts of a failed listener before capture gives up.
+const MAX_LISTENER_RESTARTS: u32 = 5;
+/// Delay before the first restart; doubled for each further one.
+const LISTENER_RESTART_DELAY: Duration = Duration::from_millis(500);
+const MAX_LISTENER_RESTART_DELAY: Duration = Duration::from_secs(30);
+/// A listener that ran this long before failing counts as healthy, so the
+/// next failure starts the backoff over.
+const LISTENER_STABLE_RUN: Duration = Duration::from_secs(60);
+
+/// Starts the capture thread. It supervises the rdev listener: when `listen`
+/// fails (e.g. macOS revoking the accessibility permission mid-session) it is
+/// restarted with exponential backoff, reported as `Capture Restarting`, until
+/// `MAX_LISTENER_RESTARTS` is exhausted and `Capture Failed` is emitted. The
+/// thread ends when `stop_capture` ends the listener; `start_capture_cmd`
+/// starts a new one.
+pub fn start_capture(app: AppHandle, state: Arc<CaptureState>) {
+    state.capture_thread_running.store(true, Ordering::SeqCst);
     thread::spawn(move || {
-        let mut last_click_time: Option<Instant> = None;
-        let mut last_click_button: Option<Button> = None;
-        let double_click_threshold = Duration::from_millis(300);
+        log::info!("Input capture thread started");
+        let pipeline = start_pipeline(app.clone(), state.clone());
+        while supervise_listener(&app, &state, pipeline.clone()) {
+            state.capture_thread_running.store(false, Ordering::SeqCst);
+            // Capture may have been started again while the listener was ending;
+            // whoever flips the flag back owns the next listener
+            if state.capture_stopped.load(Ordering::SeqCst)
+                || state.capture_thread_running.swap(true, Ordering::SeqCst)
+            {
+                log::info!("Input capture thread ended");
+                return;
+            }
+        }
+        state.capture_thread_running.store(false, Ordering::SeqCst);
+    });
+}
+
+/// Runs the rdev listener, restarting it with a growing delay when it fails.
+/// Returns `true` once it exits cleanly (ended by `stop_capture`), `false`
+/// when it has failed too often.
+fn supervise_listener(
+    app: &AppHandle,
+    state: &Arc<CaptureState>,
+    pipeline: PipelineSender,
+) -> bool {
+    let mut restarts = 0;
+    loop {
+        state.listener_running.store(true, Ordering::SeqCst);
+        let started = Instant::now();
+        let result = run_listener(app.clone(), state.clone(), pipeline.clone());
+        state.listener_running.store(false, Ordering::SeqCst);
+        let Err(error) = result else {
+            log::info!("Input capture listener exited");
+            return true;
+        };
+        log::error!("Input capture stopped: {:?}", error);
+        *state.last_error.lock().unwrap() = Some(format!("{:?}", error));
+
+        if started.elapsed() >= LISTENER_STABLE_RUN {
+            restarts = 0;
+        }
+        if restarts == MAX_LISTENER_RESTARTS {
+            log::error!("Input capture failed after {} restarts", restarts);
+            emit_system(app, state, "Capture Failed");
+            return false;
+        }
+        let delay = LISTENER_RESTART_DELAY
+            .saturating_mul(1 << restarts)
+            .min(MAX_LISTENER_RESTART_DELAY);
+        restarts += 1;
+        log::warn!(
+            "Restarting input capture in {:?} (attempt {}/{})",
+            delay,
+            restarts,
+            MAX_LISTENER_RESTARTS
+        );
+        emit_system(app, state, "Capture Restarting");
+        thread::sleep(delay);
+    }
+}
+
+/// Runs one rdev listener with fresh per-listener state. Blocks until `listen`
+/// fails or `end_listener` ends it.
+fn run_listener(
+    app: AppHandle,
+    state: Arc<CaptureState>,
+    pipeline: PipelineSender,
+) -> Result<(), rdev::ListenError> {
+    // Button, time and length of the current run of consecutive clicks
+    let mut last_click: Option<(Button, Instant, u32)> = None;
+    // Set once two buttons are held together; their releases aren't clicks
+    let mut chord_active = false;
+
+    // First-press time per held key; OS auto-repeat doesn't reset it
+    let mut key_press_times: HashMap<Key, Instant> = HashMap::new();
+    // Keys pressed as part of a shortcut, whose release shortcut-only mode still shows
+    let mut shortcut_keys: HashSet<Key> = HashSet::new();
+    let mut last_key_activity: Option<Instant> = None;
+    let mut last_key_time: Option<Instant> = None;
+
+    // Drag detection state
+    let mut drag_start_pos: Option<(f64, f64)> = None;
+    let mut is_dragging = false;
+    // Distance travelled since the button went down; a wiggle that ends near
+    // the start is still a drag
+    let mut drag_path_px = 0.0;
 
-        let mut pressed_modifiers: HashSet<Key> = HashSet::new();
-        let mut is_paused = false;
+    // Auto click-through state
+    let mut cursor_ignored: Option<bool> = None;
+    let mut last_click_through_toggle: Option<Instant> = None;
 
-        // Drag detection state
-        let mut drag_start_pos: Option<(f64, f64)> = None;
-        let mut is_dragging = false;
+    // Monitor bounds for percentage coordinates, refetched periodically
+    let mut monitors: Vec<Rect> = Vec::new();
+    let mut monitors_fetched: Option<Instant> = None;
 
-        if let Err(error) = listen(move |event| {
-            let timestamp = get_timestamp();
-            let mut payloads = Vec::new();
-            let event_name = event.name.clone();
+    // Scroll gesture coalescing state
+    let mut scroll_gesture: Option<ScrollGesture> = None;
+    let mut next_gesture_id: u64 = 0;
+    // Key, gesture id and press count of the current auto-repeat run
+    let mut key_repeat_run: Option<(Key, u64, u32)> = None;
+    // Modifiers held with no other key or button pressed yet, e.g. `Ctrl+Shift`
+    let mut modifier_tap: Option<String> = None;
+    // CapsLock as seen since the listener started; rdev can't read the
+    // initial lock state, so it's assumed off
+    let mut caps_lock = false;
+    // Accent of a dead key waiting for the key it composes with
+    let mut pending_dead_key: Option<String> = None;
 
+    // Set while capture is stopped, so per-listener state is reset on restart
+    let mut stale_after_stop = false;
+
+    // Startup settling: state is tracked but nothing is emitted until it ends
+    let capture_started = Instant::now();
+    *state.capture_started_at.lock().unwrap() = capture_started;
+    let settling = Duration::from_millis(state.settings().startup_settling_ms);
+    {
+        let app = app.clone();
+        let state = state.clone();
+        thread::spawn(move || {
+            thread::sleep(settling);
+            if state.is_armed() {
+                emit_system(&app, &state, "Capture ready");
+            }
+        });
+    }
+
+    // Registered before `listen` blocks, so `end_listener` can reach this thread
+    *state.listener_exit.lock().unwrap() = ListenerExit::current();
+    let listener_state = state.clone();
+    let result = listen(move |event| {
+        let timestamp = get_timestamp();
+        // Time already spent between the OS hook and this callback
+        let hook_delay = SystemTime::now()
+            .duration_since(event.time)
+            .unwrap_or_default();
+        let captured_at = Instant::now()
+            .checked_sub(hook_delay)
+            .unwrap_or_else(Instant::now);
+        state
+            .last_input_ms
+            .store(timestamp as u64, Ordering::Relaxed);
+        // Where `end_listener` can't remove the OS hook, a stopped capture
+        // does no work beyond this check
+        if state.capture_stopped.load(Ordering::Relaxed) {
+            stale_after_stop = true;
+            return;
+        }
+        if std::mem::take(&mut stale_after_stop) {
+            // Presses and drags in progress when capture stopped ended unseen
+            last_click = None;
+            chord_active = false;
+            key_press_times.clear();
+            drag_start_pos = None;
+            is_dragging = false;
+            drag_path_px = 0.0;
+            scroll_gesture = None;
+            key_repeat_run = None;
+            modifier_tap = None;
+            last_key_time = None;
+            pending_dead_key = None;
+        }
+        track_caps_lock(
+            &mut caps_lock,
+            &event.event_type,
+            key_press_times.contains_key(&Key::CapsLock),
+        );
+        let mut payloads = Vec::new();
+        let mut role = KeyRole::Other;
+        let is_paused = state.is_paused();
+        let event_name = event.name.clone();
+        let is_touch = is_touch_event(&event);
+
+        // Raw passthrough: only the state the hotkeys and held-input queries
+        // need is tracked, and every event is forwarded as-is
+        if state.settings().raw_passthrough {
             match event.event_type {
                 EventType::MouseMove { x, y } => {
-                    // Check drag threshold
-                    if let Some((start_x, start_y)) = drag_start_pos {
-                        if !is_dragging {
-                            let dist = ((x - start_x).powi(2) + (y - start_y).powi(2)).sqrt();
-                            if dist > 10.0 {
-                                // 10px threshold
-                                is_dragging = true;
-                                if !is_paused {
-                                    // Optionally emit DragStart
-                                    if let Some(btn) = last_click_button {
-                                        let btn_str = format!("{:?}", btn);
-                                        payloads.push(InputEventPayload {
-                                            event_type: "dragstart".to_string(),
-                                            label: format!("@DragStart[{}]", btn_str),
-                                            timestamp,
-                                        });
-                                    }
+                    *state.last_mouse_pos.lock().unwrap() = Some((x, y));
+                }
+                EventType::KeyPress(key) => {
+                    let mut pressed_modifiers = state.pressed_modifiers.lock().unwrap();
+                    if Modifier::from_key(key).is_some() {
+                        pressed_modifiers.insert(key);
+                    }
+                    if let Some(hotkey) = &state.settings().panic_hotkey {
+                        if hotkey.matches(&pressed_modifiers, key) {
+                            drop(pressed_modifiers);
+                            panic_stop(&app, &state);
+                            return;
+                        }
+                    }
+                    if state
+                        .settings()
+                        .pause_hotkey
+                        .matches(&pressed_modifiers, key)
+                    {
+                        toggle_pause_by_hotkey(&state, &pipeline, timestamp, captured_at);
+                        return;
+                    }
+                    if let Some(hotkey) = &state.settings().clear_hotkey {
+                        if hotkey.matches(&pressed_modifiers, key) {
+                            drop(pressed_modifiers);
+                            emit_clear(&app, &state);
+                            return;
+                        }
+                    }
+                }
+                EventType::KeyRelease(key) => {
+                    state.pressed_modifiers.lock().unwrap().remove(&key);
+                    key_press_times.remove(&key);
+                }
+                EventType::ButtonPress(btn) => {
+                    let mut pressed_buttons = state.pressed_buttons.lock().unwrap();
+                    if !pressed_buttons.contains(&btn) {
+                        pressed_buttons.push(btn);
+                    }
+                }
+                EventType::ButtonRelease(btn) => {
+                    state.pressed_buttons.lock().unwrap().retain(|b| *b != btn);
+                }
+                EventType::Wheel { .. } => {}
+            }
+            if is_paused || is_muted(&state.settings(), &event.event_type) || !state.is_armed() {
+                return;
+            }
+            let cursor = *state.last_mouse_pos.lock().unwrap();
+            pipeline.send(CapturedEvent {
+                payload: InputEventPayload::raw(&event.event_type, timestamp, cursor),
+                captured_at,
+                role: KeyRole::Other,
+            });
+            return;
+        }
+
+        if matches!(
+            event.event_type,
+            EventType::KeyPress(_) | EventType::KeyRelease(_)
+        ) {
+            let now = Instant::now();
+            clear_stuck_modifiers(
+                &mut state.pressed_modifiers.lock().unwrap(),
+                &mut key_press_times,
+                last_key_activity,
+                now,
+                state.settings().stuck_modifier_timeout_ms,
+            );
+            last_key_activity = Some(now);
+        }
+
+        // Ctrl+wheel zooming is not a lone Ctrl tap, even if the wheel turned while paused
+        if matches!(event.event_type, EventType::Wheel { .. }) {
+            modifier_tap = None;
+        }
+
+        match event.event_type {
+            EventType::MouseMove { x, y } => {
+                let previous_pos = state.last_mouse_pos.lock().unwrap().replace((x, y));
+                if let (Some(_), Some((px, py))) = (drag_start_pos, previous_pos) {
+                    drag_path_px += ((x - px).powi(2) + (y - py).powi(2)).sqrt();
+                }
+
+                let settings = state.settings();
+                if settings.auto_click_through {
+                    let ignore = !settings
+                        .interactive_regions
+                        .iter()
+                        .any(|region| region.contains(x, y));
+                    let debounced = last_click_through_toggle
+                        .is_some_and(|t| t.elapsed() < CLICK_THROUGH_DEBOUNCE);
+                    if cursor_ignored != Some(ignore) && !debounced {
+                        if let Some(window) = app.get_webview_window("main") {
+                            let _ = window.set_ignore_cursor_events(ignore);
+                        }
+                        cursor_ignored = Some(ignore);
+                        last_click_through_toggle = Some(Instant::now());
+                    }
+                } else {
+                    // Re-evaluate from scratch if auto mode is switched back on
+                    cursor_ignored = None;
+                }
+
+                // Check drag threshold
+                if let Some(start) = drag_start_pos {
+                    if !is_dragging {
+                        let threshold = drag_threshold_px(&state.settings(), is_touch);
+                        if drag_threshold_crossed(start, (x, y), drag_path_px, threshold) {
+                            is_dragging = true;
+                            if !is_paused {
+                                // The most recently pressed button is the one dragging
+                                let dragging_button =
+                                    state.pressed_buttons.lock().unwrap().last().copied();
+                                if let Some(btn) = dragging_button {
+                                    let btn_str = button_name(btn);
+                                    payloads.push(InputEventPayload::new(
+                                        "dragstart",
+                                        format!("@DragStart[{}]", btn_str),
+                                        timestamp,
+                                    ));
                                 }
                             }
                         }
                     }
+                }
+
+                // Passive movement right after a key press is typing jitter; a drag still shows
+                let typing = drag_start_pos.is_none()
+                    && last_key_time.is_some_and(|t| {
+                        t.elapsed() < Duration::from_millis(settings.suppress_move_while_typing_ms)
+                    });
+                if !is_paused && !typing {
+                    let display = settings.coordinate_display;
+                    if display != CoordinateDisplay::Pixels
+                        && monitors_fetched.is_none_or(|t| t.elapsed() >= MONITOR_REFRESH)
+                    {
+                        if let Ok(list) = app.available_monitors() {
+                            monitors = list
+                                .iter()
+                                .map(|m| monitor_rect(m, settings.coordinate_scale_override))
+                                .collect();
+                        }
+                        monitors_fetched = Some(Instant::now());
+                    }
+                    payloads.push(InputEventPayload::new(
+                        "mousemove",
+                        mousemove_label(
+                            display,
+                            &monitors,
+                            x,
+                            y,
+                            usize::from(settings.mouse_precision),
+                            settings.mouse_grid_px,
+                        ),
+                        timestamp,
+                    ));
+                }
+            }
+            EventType::ButtonPress(btn) => {
+                modifier_tap = None;
+                // Drags are measured from where the button went down
+                let press_pos = *state.last_mouse_pos.lock().unwrap();
+                drag_start_pos = press_pos;
+                drag_path_px = 0.0;
 
+                if !is_paused {
+                    let label = match press_pos {
+                        Some((x, y)) => {
+                            format!("@MouseDown[{} @ {:.0},{:.0}]", button_name(btn), x, y)
+                        }
+                        None => format!("@MouseDown[{}]", button_name(btn)),
+                    };
+                    payloads.push(InputEventPayload::new("mousedown", label, timestamp));
+                }
+
+                state.button_presses.fetch_add(1, Ordering::Relaxed);
+                let chord = press_button(&mut state.pressed_buttons.lock().unwrap(), btn);
+                if let Some(buttons) = chord {
+                    chord_active = true;
                     if !is_paused {
-                        payloads.push(InputEventPayload {
-                            event_type: "mousemove".to_string(),
-                            label: format!("@MouseMove[{:.0}, {:.0}]", x, y),
+                        payloads.push(InputEventPayload::new(
+                            "mousechord",
+                            format!("@MouseChord[{}]", buttons),
                             timestamp,
-                        });
+                        ));
                     }
                 }
-                EventType::ButtonPress(btn) => {
-                    // Reset drag state - we need position but don't have it easily here without tracking.
-                    // For now, let's rely on the next MouseMove to set the start position if needed,
-                    // BUT since we check threshold against start_pos, we need a start_pos.
-                    // Simple hack: We won't start tracking drag UNLESS we get a MouseMove after Click.
-                    // But we need the ORIGINAL position.
-                    // Let's rely on rdev's MouseMove x,y being sent frequently.
-                    // Actually, rdev doesn't cache position.
-                    // Improving drag logic: Use `match rdev::display_size()` is not mouse pos.
-                    // We must track last_mouse_pos from MouseMove events.
-                    // Since we are in the same closure, let's add `last_mouse_pos` state.
-
-                    // Note: This requires last_mouse_pos to have been captured at least once.
-                    // We'll skip setting drag_start_pos if we haven't seen a move yet.
 
-                    if !is_paused {
-                        let btn_str = format!("{:?}", btn);
-                        payloads.push(InputEventPayload {
-                            event_type: "mousedown".to_string(),
-                            label: format!("@MouseDown[{}]", btn_str),
+                is_dragging = false;
+            }
+            EventType::ButtonRelease(btn) => {
+                let btn_str = button_name(btn);
+
+                if !is_paused {
+                    payloads.push(InputEventPayload::new(
+                        "mouseup",
+                        format!("@MouseUp[{}]", btn_str),
+                        timestamp,
+                    ));
+
+                    if is_dragging {
+                        let end_pos = *state.last_mouse_pos.lock().unwrap();
+                        let payload = match (drag_start_pos, end_pos) {
+                            (Some((start_x, start_y)), Some((end_x, end_y))) => {
+                                InputEventPayload::new(
+                                    "drag",
+                                    format!(
+                                        "@Drag[{} ({:.0},{:.0})->({:.0},{:.0})]",
+                                        btn_str, start_x, start_y, end_x, end_y
+                                    ),
+                                    timestamp,
+                                )
+                                .with_drag_start((start_x, start_y))
+                            }
+                            _ => InputEventPayload::new(
+                                "drag",
+                                format!("@Drag[{}]", btn_str),
+                                timestamp,
+                            ),
+                        };
+                        payloads.push(payload);
+                    } else if !chord_active {
+                        let now = Instant::now();
+                        let settings = state.settings();
+                        let click_count = click_count(
+                            last_click,
+                            btn,
+                            now,
+                            Duration::from_millis(settings.double_click_threshold_ms),
+                        );
+                        // Modifier-clicks (Ctrl+Left, Shift+Left, ...) are shown as one gesture
+                        let clicked = format!(
+                            "{}{}",
+                            modifier_prefix(&state.pressed_modifiers.lock().unwrap(), &settings),
+                            btn_str
+                        );
+                        payloads.extend(click_payloads(
+                            click_count,
+                            settings.suppress_click_on_doubleclick,
+                            &clicked,
                             timestamp,
-                        });
+                        ));
+                        // A fourth click starts a new run
+                        last_click = (click_count < 3).then_some((btn, now, click_count));
                     }
+                }
 
-                    last_click_button = Some(btn);
-                    is_dragging = false;
-                    // To implement drag properly we need last_mouse_pos.
-                    // See below modification to MouseMove to store it.
+                let buttons_still_held =
+                    release_button(&mut state.pressed_buttons.lock().unwrap(), btn);
+                if !buttons_still_held {
+                    chord_active = false;
                 }
-                EventType::ButtonRelease(btn) => {
-                    let btn_str = format!("{:?}", btn);
 
-                    if !is_paused {
-                        payloads.push(InputEventPayload {
-                            event_type: "mouseup".to_string(),
-                            label: format!("@MouseUp[{}]", btn_str),
-                            timestamp,
-                        });
+                // A button still held can start a new drag from here
+                drag_start_pos = if buttons_still_held {
+                    *state.last_mouse_pos.lock().unwrap()
+                } else {
+                    None
+                };
+                drag_path_px = 0.0;
+                is_dragging = false;
+            }
+            EventType::Wheel { delta_x, delta_y } if !is_paused => {
+                let delta_px =
+                    (delta_x.unsigned_abs() + delta_y.unsigned_abs()) * PIXELS_PER_SCROLL_NOTCH;
+                let previous = state
+                    .scroll_distance_px
+                    .fetch_add(delta_px, Ordering::Relaxed);
+                let total = previous + delta_px;
 
-                        if is_dragging {
-                            payloads.push(InputEventPayload {
-                                event_type: "drag".to_string(),
-                                label: format!("@Drag[{}]", btn_str),
-                                timestamp,
-                            });
-                        } else {
-                            payloads.push(InputEventPayload {
-                                event_type: "click".to_string(),
-                                label: format!("@Click[{}]", btn_str),
-                                timestamp,
-                            });
+                // Emit a milestone each time the total crosses a multiple of the interval
+                let milestone = state.settings().scroll_milestone_px;
+                if milestone > 0 && total / milestone > previous / milestone {
+                    payloads.push(InputEventPayload::new(
+                        "scrolled",
+                        format!("@Scrolled[{} px]", total / milestone * milestone),
+                        timestamp,
+                    ));
+                }
 
-                            let now = Instant::now();
-                            if let (Some(last_time), Some(last_btn)) =
-                                (last_click_time, last_click_button)
-                            {
-                                if last_btn == btn
-                                    && now.duration_since(last_time) < double_click_threshold
-                                {
-                                    payloads.push(InputEventPayload {
-                                        event_type: "doubleclick".to_string(),
-                                        label: format!("@DoubleClick[{}]", btn_str),
-                                        timestamp,
-                                    });
-                                }
+                if let Some(direction) = scroll_direction(delta_x, delta_y) {
+                    let settings = state.settings();
+                    let gap = Duration::from_millis(settings.scroll_gesture_gap_ms);
+                    // Ctrl+Scroll (zoom) and Shift+Scroll (pan) are shown as their own gestures
+                    let modifiers = if settings.scroll_modifiers {
+                        modifier_prefix(&state.pressed_modifiers.lock().unwrap(), &settings)
+                    } else {
+                        String::new()
+                    };
+                    // A diagonal scroll is shown along its dominant axis only
+                    let axis_px = delta_x.unsigned_abs().max(delta_y.unsigned_abs())
+                        * PIXELS_PER_SCROLL_NOTCH;
+                    let now = Instant::now();
+                    let gesture = match scroll_gesture.take() {
+                        Some(mut g)
+                            if g.direction == direction
+                                && g.modifiers == modifiers
+                                && now.duration_since(g.last_at) < gap =>
+                        {
+                            g.last_at = now;
+                            g.distance_px += axis_px;
+                            g
+                        }
+                        _ => {
+                            next_gesture_id += 1;
+                            ScrollGesture {
+                                id: next_gesture_id,
+                                direction,
+                                modifiers,
+                                last_at: now,
+                                distance_px: axis_px,
                             }
-                            last_click_time = Some(now);
                         }
+                    };
+                    let magnitude = match settings.scroll_step_px {
+                        Some(step) => format!("{:.0}", gesture.distance_px as f64 / step),
+                        None => format!("{}px", gesture.distance_px),
+                    };
+                    payloads.push(
+                        InputEventPayload::new(
+                            "scroll",
+                            format!(
+                                "@Scroll[{}{} {}]",
+                                gesture.modifiers, gesture.direction, magnitude
+                            ),
+                            timestamp,
+                        )
+                        .with_gesture(gesture.id),
+                    );
+                    scroll_gesture = Some(gesture);
+                }
+            }
+            EventType::KeyPress(key) => {
+                let is_repeat = record_key_press(&mut key_press_times, key, Instant::now());
+                last_key_time = Some(Instant::now());
+                if Modifier::from_key(key).is_none() {
+                    modifier_tap = None;
+                }
+                let mut pressed_modifiers = state.pressed_modifiers.lock().unwrap();
+
+                if matches!(
+                    key,
+                    Key::ControlLeft
+                        | Key::ControlRight
+                        | Key::ShiftLeft
+                        | Key::ShiftRight
+                        | Key::Alt
+                        | Key::MetaLeft
+                        | Key::MetaRight
+                ) {
+                    pressed_modifiers.insert(key);
+                }
+
+                // Panic stop is checked before anything else can emit
+                if let Some(hotkey) = &state.settings().panic_hotkey {
+                    if hotkey.matches(&pressed_modifiers, key) {
+                        panic_stop(&app, &state);
+                        return;
                     }
+                }
 
-                    drag_start_pos = None;
-                    is_dragging = false;
-                    last_click_button = Some(btn);
+                if state
+                    .settings()
+                    .pause_hotkey
+                    .matches(&pressed_modifiers, key)
+                {
+                    toggle_pause_by_hotkey(&state, &pipeline, timestamp, captured_at);
+                    return;
                 }
-                EventType::KeyPress(key) => {
-                    if matches!(
+
+                if let Some(hotkey) = &state.settings().clear_hotkey {
+                    if hotkey.matches(&pressed_modifiers, key) {
+                        drop(pressed_modifiers);
+                        emit_clear(&app, &state);
+                        return;
+                    }
+                }
+
+                let is_ctrl = pressed_modifiers.contains(&Key::ControlLeft)
+                    || pressed_modifiers.contains(&Key::ControlRight);
+                let is_alt = pressed_modifiers.contains(&Key::Alt);
+
+                // Muted keys never produce events, whatever the context
+                if is_muted(&state.settings(), &event.event_type) {
+                    log::trace!("Dropped muted key {:?}", key);
+                    return;
+                }
+
+                if !is_repeat && Modifier::from_key(key).is_none() {
+                    state.key_presses.fetch_add(1, Ordering::Relaxed);
+                }
+
+                if !is_paused && !is_repeat {
+                    count_heatmap_press(&state, key);
+                }
+
+                let repeat_mode = state.settings().key_repeat_mode;
+                let suppress_repeat = is_repeat && repeat_mode == KeyRepeatMode::Suppress;
+                if !is_paused && !suppress_repeat {
+                    let mut key_parts: Vec<String> = Vec::new();
+
+                    let is_shift = pressed_modifiers.contains(&Key::ShiftLeft)
+                        || pressed_modifiers.contains(&Key::ShiftRight);
+                    let is_meta = pressed_modifiers.contains(&Key::MetaLeft)
+                        || pressed_modifiers.contains(&Key::MetaRight);
+
+                    let is_modifier_key = matches!(
                         key,
                         Key::ControlLeft
                             | Key::ControlRight
@@ -154,406 +2175,1062 @@ pub fn start_capture(app: AppHandle) {
                             | Key::Alt
                             | Key::MetaLeft
                             | Key::MetaRight
-                    ) {
-                        pressed_modifiers.insert(key);
-                    }
+                    );
 
-                    let is_ctrl = pressed_modifiers.contains(&Key::ControlLeft)
-                        || pressed_modifiers.contains(&Key::ControlRight);
-                    let is_alt = pressed_modifiers.contains(&Key::Alt);
-                    if is_ctrl && is_alt && key == Key::KeyP {
-                        is_paused = !is_paused;
-                        let status_label = if is_paused { "Paused" } else { "Resumed" };
-                        let _ = app.emit(
-                            "input-event",
-                            InputEventPayload {
-                                event_type: "system".to_string(),
-                                label: format!("Capture {}", status_label),
-                                timestamp,
-                            },
-                        );
-                        return;
-                    }
+                    let mut final_key_string = String::new();
+                    let mut consumes_shift = false;
+                    // Names from the OS already include any dead-key composition
+                    let mut named_by_os = false;
 
-                    if !is_paused {
-                        let mut key_parts: Vec<String> = Vec::new();
-
-                        let is_shift = pressed_modifiers.contains(&Key::ShiftLeft)
-                            || pressed_modifiers.contains(&Key::ShiftRight);
-                        let is_meta = pressed_modifiers.contains(&Key::MetaLeft)
-                            || pressed_modifiers.contains(&Key::MetaRight);
-
-                        let is_modifier_key = matches!(
-                            key,
-                            Key::ControlLeft
-                                | Key::ControlRight
-                                | Key::ShiftLeft
-                                | Key::ShiftRight
-                                | Key::Alt
-                                | Key::MetaLeft
-                                | Key::MetaRight
-                        );
+                    // Primary Strategy: Use OS-provided name if available and not a control char
+                    // This handles JIS layout and Shift states auto-magically
+                    // (can be turned off for platforms that report wrong names)
+                    let os_name = event_name
+                        .as_ref()
+                        .filter(|_| state.settings().prefer_os_name);
+                    if let Some(name) = os_name {
+                        // Filter out control characters if necessary, though rdev usually returns None for pure modifiers
+                        // But keeps things like Enter/Tab sometimes? Logs showed Escape -> \u{1b}
+                        // Let's check string length and content.
+                        // We want to use it for printable characters.
+                        let is_control_char = name.chars().any(|c| c.is_control());
 
-                        let mut final_key_string = String::new();
-                        let mut consumes_shift = false;
-
-                        // Primary Strategy: Use OS-provided name if available and not a control char
-                        // This handles JIS layout and Shift states auto-magically
-                        if let Some(ref name) = event_name {
-                            // Filter out control characters if necessary, though rdev usually returns None for pure modifiers
-                            // But keeps things like Enter/Tab sometimes? Logs showed Escape -> \u{1b}
-                            // Let's check string length and content.
-                            // We want to use it for printable characters.
-                            let is_control_char = name.chars().any(|c| c.is_control());
-
-                            if !is_control_char && !name.is_empty() {
-                                final_key_string = name.clone();
-                                // If we use the OS name, implicit assumption is that it includes the shift state
-                                // e.g. Shift+a -> "A". user wants just "A".
-                                // So we say it consumes_shift.
-                                consumes_shift = true;
-                            }
+                        if !is_control_char && !name.is_empty() {
+                            final_key_string = name.clone();
+                            // If we use the OS name, implicit assumption is that it includes the shift state
+                            // e.g. Shift+a -> "A". user wants just "A".
+                            // So we say it consumes_shift.
+                            consumes_shift = true;
+                            named_by_os = true;
                         }
+                    }
 
-                        // Exception: Space should be explicitly "Space"
-                        if key == Key::Space {
-                            final_key_string = "Space".to_string();
-                            consumes_shift = false;
+                    // A dead key (e.g. `^` on AZERTY) types nothing by itself; the
+                    // composed character (`ê`) is emitted with the next key press
+                    if final_key_string.is_empty() && !is_ctrl && !is_alt && !is_meta {
+                        if let Some(mark) = dead_key_mark(key, is_shift, &state.settings()) {
+                            log::trace!("Dead key {:?} held back for composition", key);
+                            pending_dead_key = Some(mark);
+                            return;
                         }
+                    }
+                    let dead_mark = if is_modifier_key {
+                        None
+                    } else {
+                        pending_dead_key.take()
+                    };
 
-                        // Fallback Strategy: Manual Mapping (for control chars or when name is None)
-
-                        // Helper closure for character mapping
-                        // Returns Some((string, consumes_shift)) if mapped, None otherwise
-                        let get_jis_char = |k: Key, shift: bool| -> Option<(String, bool)> {
-                            match k {
-                                Key::KeyA => {
-                                    Some((if shift { "A" } else { "a" }.to_string(), true))
-                                }
-                                Key::KeyB => {
-                                    Some((if shift { "B" } else { "b" }.to_string(), true))
-                                }
-                                Key::KeyC => {
-                                    Some((if shift { "C" } else { "c" }.to_string(), true))
-                                }
-                                Key::KeyD => {
-                                    Some((if shift { "D" } else { "d" }.to_string(), true))
-                                }
-                                Key::KeyE => {
-                                    Some((if shift { "E" } else { "e" }.to_string(), true))
-                                }
-                                Key::KeyF => {
-                                    Some((if shift { "F" } else { "f" }.to_string(), true))
-                                }
-                                Key::KeyG => {
-                                    Some((if shift { "G" } else { "g" }.to_string(), true))
-                                }
-                                Key::KeyH => {
-                                    Some((if shift { "H" } else { "h" }.to_string(), true))
-                                }
-                                Key::KeyI => {
-                                    Some((if shift { "I" } else { "i" }.to_string(), true))
-                                }
-                                Key::KeyJ => {
-                                    Some((if shift { "J" } else { "j" }.to_string(), true))
-                                }
-                                Key::KeyK => {
-                                    Some((if shift { "K" } else { "k" }.to_string(), true))
-                                }
-                                Key::KeyL => {
-                                    Some((if shift { "L" } else { "l" }.to_string(), true))
-                                }
-                                Key::KeyM => {
-                                    Some((if shift { "M" } else { "m" }.to_string(), true))
-                                }
-                                Key::KeyN => {
-                                    Some((if shift { "N" } else { "n" }.to_string(), true))
-                                }
-                                Key::KeyO => {
-                                    Some((if shift { "O" } else { "o" }.to_string(), true))
-                                }
-                                Key::KeyP => {
-                                    Some((if shift { "P" } else { "p" }.to_string(), true))
-                                }
-                                Key::KeyQ => {
-                                    Some((if shift { "Q" } else { "q" }.to_string(), true))
-                                }
-                                Key::KeyR => {
-                                    Some((if shift { "R" } else { "r" }.to_string(), true))
-                                }
-                                Key::KeyS => {
-                                    Some((if shift { "S" } else { "s" }.to_string(), true))
-                                }
-                                Key::KeyT => {
-                                    Some((if shift { "T" } else { "t" }.to_string(), true))
-                                }
-                                Key::KeyU => {
-                                    Some((if shift { "U" } else { "u" }.to_string(), true))
-                                }
-                                Key::KeyV => {
-                                    Some((if shift { "V" } else { "v" }.to_string(), true))
-                                }
-                                Key::KeyW => {
-                                    Some((if shift { "W" } else { "w" }.to_string(), true))
-                                }
-                                Key::KeyX => {
-                                    Some((if shift { "X" } else { "x" }.to_string(), true))
-                                }
-                                Key::KeyY => {
-                                    Some((if shift { "Y" } else { "y" }.to_string(), true))
-                                }
-                                Key::KeyZ => {
-                                    Some((if shift { "Z" } else { "z" }.to_string(), true))
-                                }
-
-                                // Common JIS Numbers
-                                Key::Num1 => {
-                                    Some((if shift { "!" } else { "1" }.to_string(), true))
-                                }
-                                Key::Num2 => {
-                                    Some((if shift { "\"" } else { "2" }.to_string(), true))
-                                }
-                                Key::Num3 => {
-                                    Some((if shift { "#" } else { "3" }.to_string(), true))
-                                }
-                                Key::Num4 => {
-                                    Some((if shift { "$" } else { "4" }.to_string(), true))
-                                }
-                                Key::Num5 => {
-                                    Some((if shift { "%" } else { "5" }.to_string(), true))
-                                }
-                                Key::Num6 => {
-                                    Some((if shift { "&" } else { "6" }.to_string(), true))
-                                }
-                                Key::Num7 => {
-                                    Some((if shift { "'" } else { "7" }.to_string(), true))
-                                }
-                                Key::Num8 => {
-                                    Some((if shift { "(" } else { "8" }.to_string(), true))
-                                }
-                                Key::Num9 => {
-                                    Some((if shift { ")" } else { "9" }.to_string(), true))
-                                }
-                                Key::Num0 => {
-                                    Some((if shift { "0" } else { "0" }.to_string(), false))
-                                }
+                    // Exception: Space should be explicitly "Space"
+                    if key == Key::Space {
+                        final_key_string = "Space".to_string();
+                        consumes_shift = false;
+                        named_by_os = false;
+                    }
+                    // The OS names numpad keys like the top row ("7"); keep them apart
+                    if is_numpad_key(key) {
+                        final_key_string = get_default_key_name(key).to_string();
+                        consumes_shift = false;
+                    }
 
-                                // JIS Symbol Mappings
-                                Key::BackQuote => {
-                                    Some((if shift { "`" } else { "@" }.to_string(), true))
-                                }
-                                Key::LeftBracket => {
-                                    Some((if shift { "{" } else { "[" }.to_string(), true))
-                                }
-                                Key::RightBracket => {
-                                    Some((if shift { "}" } else { "]" }.to_string(), true))
-                                }
-                                Key::BackSlash => {
-                                    Some((if shift { "}" } else { "]" }.to_string(), true))
-                                }
-                                Key::Quote => {
-                                    Some((if shift { "*" } else { ":" }.to_string(), true))
+                    // Fallback Strategy: Manual Mapping (for control chars or when name is None)
+                    // from the selected layout's table; JIS when none is selected
+                    let fallback_layout = state
+                        .settings()
+                        .keyboard_layout
+                        .unwrap_or(KeyboardLayout::Jis);
+                    if !is_modifier_key {
+                        let has_other_modifiers = is_ctrl || is_alt || is_meta;
+                        if !has_other_modifiers {
+                            // Typewriter mode
+                            let mut shows_both_names = false;
+                            if !final_key_string.is_empty() {
+                                // Set by event.name; check it against the table
+                                let manual = table_disagreement(
+                                    fallback_layout,
+                                    key,
+                                    is_shift,
+                                    caps_lock,
+                                    &final_key_string,
+                                );
+                                if let Some((text, consumed)) = manual {
+                                    state.record_name_conflict(
+                                        key,
+                                        is_shift,
+                                        &final_key_string,
+                                        &text,
+                                    );
+                                    (final_key_string, consumes_shift, shows_both_names) =
+                                        resolve_name_conflict(
+                                            state.settings().name_conflict_policy,
+                                            (final_key_string, consumes_shift),
+                                            (text, consumed && is_shift),
+                                        );
                                 }
-                                Key::SemiColon => {
-                                    Some((if shift { "+" } else { ";" }.to_string(), true))
-                                }
-                                Key::Comma => {
-                                    Some((if shift { "<" } else { "," }.to_string(), true))
-                                }
-                                Key::Dot => Some((if shift { ">" } else { "." }.to_string(), true)),
-                                Key::Slash => {
-                                    Some((if shift { "?" } else { "/" }.to_string(), true))
-                                }
-                                Key::Minus => {
-                                    Some((if shift { "=" } else { "-" }.to_string(), true))
-                                }
-                                Key::Equal => {
-                                    Some((if shift { "~" } else { "^" }.to_string(), true))
-                                }
-
-                                // Standardize others
-                                Key::Space => Some(("Space".to_string(), false)),
-                                Key::Return => Some(("Enter".to_string(), false)),
-                                Key::Backspace => Some(("Backspace".to_string(), false)),
-                                Key::Tab => Some(("Tab".to_string(), false)),
-                                Key::Escape => Some(("Esc".to_string(), false)),
-
-                                _ => None,
+                            } else if let Some((text, consumed)) =
+                                layout_char(fallback_layout, key, is_shift)
+                            {
+                                final_key_string = apply_caps_lock(text, caps_lock);
+                                consumes_shift = consumed && is_shift;
+                            } else {
+                                final_key_string = get_default_key_name(key).to_string();
                             }
-                        };
-
-                        let get_default_key_name = |k: Key| -> &str {
-                            match k {
-                                Key::KeyA => "A",
-                                Key::KeyB => "B",
-                                Key::KeyC => "C",
-                                Key::KeyD => "D",
-                                Key::KeyE => "E",
-                                Key::KeyF => "F",
-                                Key::KeyG => "G",
-                                Key::KeyH => "H",
-                                Key::KeyI => "I",
-                                Key::KeyJ => "J",
-                                Key::KeyK => "K",
-                                Key::KeyL => "L",
-                                Key::KeyM => "M",
-                                Key::KeyN => "N",
-                                Key::KeyO => "O",
-                                Key::KeyP => "P",
-                                Key::KeyQ => "Q",
-                                Key::KeyR => "R",
-                                Key::KeyS => "S",
-                                Key::KeyT => "T",
-                                Key::KeyU => "U",
-                                Key::KeyV => "V",
-                                Key::KeyW => "W",
-                                Key::KeyX => "X",
-                                Key::KeyY => "Y",
-                                Key::KeyZ => "Z",
-                                Key::Num1 => "1",
-                                Key::Num2 => "2",
-                                Key::Num3 => "3",
-                                Key::Num4 => "4",
-                                Key::Num5 => "5",
-                                Key::Num6 => "6",
-                                Key::Num7 => "7",
-                                Key::Num8 => "8",
-                                Key::Num9 => "9",
-                                Key::Num0 => "0",
-                                Key::Space => "Space",
-                                Key::Return => "Enter",
-                                Key::Backspace => "Backspace",
-                                Key::Tab => "Tab",
-                                Key::Escape => "Esc",
-                                Key::UpArrow => "Up",
-                                Key::DownArrow => "Down",
-                                Key::LeftArrow => "Left",
-                                Key::RightArrow => "Right",
-                                Key::Minus => "-",
-                                Key::Equal => "=",
-                                Key::LeftBracket => "[",
-                                Key::RightBracket => "]",
-                                Key::BackSlash => "\\",
-                                Key::SemiColon => ";",
-                                Key::Quote => "'",
-                                Key::BackQuote => "`",
-                                Key::Comma => ",",
-                                Key::Dot => ".",
-                                Key::Slash => "/",
-                                Key::F1 => "F1",
-                                Key::F2 => "F2",
-                                Key::F3 => "F3",
-                                Key::F4 => "F4",
-                                Key::F5 => "F5",
-                                Key::F6 => "F6",
-                                Key::F7 => "F7",
-                                Key::F8 => "F8",
-                                Key::F9 => "F9",
-                                Key::F10 => "F10",
-                                Key::F11 => "F11",
-                                Key::F12 => "F12",
-                                _ => "?",
+                            if let Some(mark) = dead_mark.filter(|_| !named_by_os) {
+                                final_key_string = compose_dead_key(&mark, &final_key_string);
+                            }
+                            if final_key_string.chars().count() == 1
+                                || matches!(key, Key::Space | Key::Return | Key::Tab)
+                            {
+                                role = KeyRole::Text;
                             }
-                        };
 
-                        if !is_modifier_key {
-                            let has_other_modifiers = is_ctrl || is_alt || is_meta;
-                            if !has_other_modifiers {
-                                // Typewriter mode
-                                if !final_key_string.is_empty() {
-                                    // already set by event.name
-                                } else if let Some((text, consumed)) = get_jis_char(key, is_shift) {
-                                    final_key_string = text;
-                                    consumes_shift = consumed && is_shift;
-                                } else {
-                                    final_key_string = get_default_key_name(key).to_string();
-                                }
-                            } else {
-                                // Shortcut mode - use default uppercase/symbols
-                                // E.g. Ctrl+S -> we want "Ctrl+S", usually name might be "s" or "S" or control-code
-                                // For shortcuts, typically we want the Key name (e.g. "S"), not the produced char (which might be affected by ctrl)
-                                // So we ignore event.name for Shortcuts and force default key name logic?
-                                // OR we use default key name always for shortcuts.
-                                final_key_string = get_default_key_name(key).to_string();
-                                consumes_shift = false; // Shortcuts like Ctrl+Shift+S explicitely show Shift
+                            // Privacy mode hides what was typed, not that typing happened
+                            let settings = state.settings();
+                            let reveals_text = final_key_string.chars().count() == 1
+                                || shows_both_names
+                                || (is_numpad_key(key) && key != Key::KpReturn);
+                            if settings.privacy_mode && reveals_text {
+                                final_key_string = settings.privacy_mask.clone();
+                                // Shift would reveal the case
+                                consumes_shift = true;
                             }
+                        } else {
+                            // Shortcut mode - use default uppercase/symbols
+                            // E.g. Ctrl+S -> we want "Ctrl+S", usually name might be "s" or "S" or control-code
+                            // For shortcuts, typically we want the Key name (e.g. "S"), not the produced char (which might be affected by ctrl)
+                            // So we ignore event.name for Shortcuts and force default key name logic?
+                            // OR we use default key name always for shortcuts.
+                            final_key_string = get_default_key_name(key).to_string();
+                            consumes_shift = false; // Shortcuts like Ctrl+Shift+S explicitely show Shift
+                            role = KeyRole::Shortcut;
                         }
+                    }
 
-                        if is_ctrl {
-                            key_parts.push("Ctrl".to_string());
-                        }
-                        if is_alt {
-                            key_parts.push("Alt".to_string());
+                    let settings = state.settings();
+                    for modifier in &settings.modifier_order {
+                        let held = match modifier {
+                            Modifier::Ctrl => is_ctrl,
+                            Modifier::Alt => is_alt,
+                            Modifier::Shift => is_shift && !consumes_shift,
+                            Modifier::Meta => is_meta,
+                        };
+                        if held {
+                            key_parts.push(modifier_label(
+                                &pressed_modifiers,
+                                *modifier,
+                                &settings,
+                            ));
                         }
-                        if is_shift && !consumes_shift {
-                            key_parts.push("Shift".to_string());
+                    }
+
+                    if !is_modifier_key {
+                        if final_key_string == "?" {
+                            key_parts.push(key_display_name(key));
+                        } else {
+                            key_parts.push(final_key_string);
                         }
-                        if is_meta {
-                            key_parts.push("Meta".to_string());
+                    } else {
+                        // A modifier alone is only shown as a tap, on release
+                        if !is_repeat && !key_parts.is_empty() {
+                            modifier_tap = Some(key_parts.join("+"));
                         }
+                        return;
+                    }
 
-                        if !is_modifier_key {
-                            if final_key_string == "?" {
-                                let s = format!("{:?}", key);
-                                let clean_s = if s.starts_with("Key") && s.len() > 3 {
-                                    s[3..].to_string()
-                                } else {
-                                    s
-                                };
-                                key_parts.push(clean_s);
-                            } else {
-                                key_parts.push(final_key_string);
+                    if role == KeyRole::Shortcut {
+                        shortcut_keys.insert(key);
+                    }
+                    // Shift+letter is typing too; only Ctrl/Alt/Meta combos are shortcuts
+                    if settings.shortcut_only && role != KeyRole::Shortcut {
+                        log::trace!("Dropped key {:?}: shortcut-only mode", key);
+                        return;
+                    }
+
+                    if !key_parts.is_empty() {
+                        let mut label = format!("@Key[{}]", key_parts.join("+"));
+                        let mut gesture = None;
+                        if repeat_mode == KeyRepeatMode::Count {
+                            let (_, id, count) = match key_repeat_run {
+                                Some((run_key, id, count)) if is_repeat && run_key == key => {
+                                    (key, id, count + 1)
+                                }
+                                _ => {
+                                    next_gesture_id += 1;
+                                    (key, next_gesture_id, 1)
+                                }
+                            };
+                            key_repeat_run = Some((key, id, count));
+                            if count > 1 {
+                                label = format!("@Key[{} ×{}]", key_parts.join("+"), count);
+                                // Repeats arrive faster than typing; keep them out of paste runs
+                                role = KeyRole::Other;
                             }
-                        } else if key_parts.is_empty() {
-                            return;
+                            gesture = Some(id);
+                        }
+                        // Same label, but typed so the overlay can style combos apart from typing
+                        let event_type = if role == KeyRole::Shortcut {
+                            "shortcut"
+                        } else {
+                            "key"
+                        };
+                        let mut payload = InputEventPayload::new(event_type, label, timestamp);
+                        if let Some(id) = gesture {
+                            payload = payload.with_gesture(id);
                         }
+                        if role == KeyRole::Shortcut {
+                            payload.modifier_hold_ms =
+                                Some(modifier_hold_ms(&key_press_times, Instant::now()));
+                        }
+                        payloads.push(payload);
+                    }
+                }
+            }
+            EventType::KeyRelease(key) => {
+                let mut pressed_modifiers = state.pressed_modifiers.lock().unwrap();
+                if matches!(
+                    key,
+                    Key::ControlLeft
+                        | Key::ControlRight
+                        | Key::ShiftLeft
+                        | Key::ShiftRight
+                        | Key::Alt
+                        | Key::MetaLeft
+                        | Key::MetaRight
+                ) {
+                    pressed_modifiers.remove(&key);
+                }
 
-                        if !key_parts.is_empty() {
-                            let label = format!("@Key[{}]", key_parts.join("+"));
-                            payloads.push(InputEventPayload {
-                                event_type: "key".to_string(),
-                                label,
+                // Released before any other key or button: a modifier tap
+                if Modifier::from_key(key).is_some() {
+                    if let Some(tap) = modifier_tap.take() {
+                        let settings = state.settings();
+                        if !is_paused && settings.show_lone_modifiers && !settings.shortcut_only {
+                            payloads.push(InputEventPayload::new(
+                                "key",
+                                format!("@Key[{}]", tap),
                                 timestamp,
-                            });
+                            ));
                         }
                     }
                 }
-                EventType::KeyRelease(key) => {
-                    if matches!(
-                        key,
-                        Key::ControlLeft
-                            | Key::ControlRight
-                            | Key::ShiftLeft
-                            | Key::ShiftRight
-                            | Key::Alt
-                            | Key::MetaLeft
-                            | Key::MetaRight
-                    ) {
-                        pressed_modifiers.remove(&key);
+
+                let was_shortcut = shortcut_keys.remove(&key);
+                if let Some(held_for) = key_held_for(&mut key_press_times, key, Instant::now()) {
+                    let settings = state.settings();
+                    let key_name = released_key_name(&settings, key);
+                    // Releasing a typed key is typing too
+                    let hidden = settings.shortcut_only && !was_shortcut;
+                    if !is_paused && !hidden && !is_muted(&settings, &event.event_type) {
+                        if settings.emit_key_release {
+                            role = KeyRole::Release;
+                            payloads.push(InputEventPayload::new(
+                                "keyup",
+                                keyup_label(&key_name, held_for),
+                                timestamp,
+                            ));
+                        }
+                        // Modifiers are held as part of every shortcut, so only other keys count
+                        let hold_threshold = settings.key_hold_threshold_ms;
+                        if hold_threshold > 0
+                            && Modifier::from_key(key).is_none()
+                            && held_for >= Duration::from_millis(hold_threshold)
+                        {
+                            payloads.push(InputEventPayload::new(
+                                "keyhold",
+                                format!("@KeyHold[{} {:.1}s]", key_name, held_for.as_secs_f64()),
+                                timestamp,
+                            ));
+                        }
                     }
                 }
-                _ => {}
             }
+            _ => {}
+        }
 
-            // Important: We need to capture MouseMove X,Y to use for drag start position later
-            // However, we can't mutate drag_start_pos easily if it was None.
-            // Let's modify the loop structure slightly to handle this.
-            if let EventType::MouseMove { x, y } = event.event_type {
-                // If we just pressed (drag_start_pos is None but button is pressed?), set it?
-                // But drag_start_pos is reset on press/release.
-                // We actually need to set drag_start_pos ON PRESS using current location.
-                // But we don't have location on press.
-                // So we set it on FIRST move after press.
-                if last_click_button.is_some() && drag_start_pos.is_none() && !is_dragging {
-                    drag_start_pos = Some((x, y));
-                }
+        let settings = state.settings();
+        retain_in_roi(
+            &settings,
+            *state.last_mouse_pos.lock().unwrap(),
+            &mut payloads,
+        );
+
+        if capture_started.elapsed() < settling {
+            if !payloads.is_empty() {
+                log::trace!("Dropped {} event(s): startup settling", payloads.len());
             }
+            return;
+        }
 
-            for p in payloads {
-                let _ = app.emit("input-event", p);
+        // Disarmed capture keeps tracking state but emits nothing
+        if !state.is_armed() {
+            if !payloads.is_empty() {
+                log::trace!("Dropped {} event(s): capture disarmed", payloads.len());
+            }
+            return;
+        }
+
+        let layout = state.settings().keyboard_layout.map(|l| l.id().to_string());
+        let cursor = *state.last_mouse_pos.lock().unwrap();
+        let window_frame = *state.focused_window.lock().unwrap();
+        let raw = if settings.include_raw {
+            match event.event_type {
+                // The physical key would give away what privacy mode masks
+                EventType::KeyPress(_) | EventType::KeyRelease(_) if settings.privacy_mode => None,
+                EventType::KeyPress(key) | EventType::KeyRelease(key) => Some(format!("{:?}", key)),
+                EventType::ButtonPress(btn) | EventType::ButtonRelease(btn) => {
+                    Some(format!("{:?}", btn))
+                }
+                _ => None,
             }
-        }) {
-            eprintln!("Input capture error: {:?}", error);
+        } else {
+            None
+        };
+        for mut payload in payloads {
+            payload.layout = layout.clone();
+            payload.raw = raw.clone();
+            if let (true, Some(position)) = (is_mouse_event(&payload.event_type), cursor) {
+                payload = payload.at_position(position, window_frame);
+            }
+            pipeline.send(CapturedEvent {
+                payload,
+                captured_at,
+                role,
+            });
         }
     });
+    listener_state.listener_exit.lock().unwrap().take();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Asserts `table`'s unshifted and shifted text for each `(key, plain, shifted)`.
+    fn assert_chars(table: fn(Key, bool) -> Option<(String, bool)>, rows: &[(Key, &str, &str)]) {
+        for &(key, plain, shifted) in rows {
+            let text = |shift| table(key, shift).map(|(text, _)| text);
+            assert_eq!(text(false).as_deref(), Some(plain), "{:?}", key);
+            assert_eq!(text(true).as_deref(), Some(shifted), "{:?} with Shift", key);
+        }
+    }
+
+    #[test]
+    fn jis_symbol_row() {
+        assert_chars(
+            get_jis_char,
+            &[
+                (Key::Num1, "1", "!"),
+                (Key::Num2, "2", "\""),
+                (Key::Num3, "3", "#"),
+                (Key::Num4, "4", "$"),
+                (Key::Num5, "5", "%"),
+                (Key::Num6, "6", "&"),
+                (Key::Num7, "7", "'"),
+                (Key::Num8, "8", "("),
+                (Key::Num9, "9", ")"),
+                (Key::Num0, "0", "0"),
+                (Key::Minus, "-", "="),
+                (Key::IntlBackslash, "\\", "_"),
+            ],
+        );
+        // Shift+0 types nothing on JIS, so Shift stays visible
+        assert_eq!(
+            get_jis_char(Key::Num0, true),
+            Some(("0".to_string(), false))
+        );
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn jis_yen_key_is_not_a_bracket() {
+        assert_chars(get_jis_char, &[(Key::BackSlash, "¥", "|")]);
+        assert_ne!(
+            get_jis_char(Key::BackSlash, false),
+            get_jis_char(Key::RightBracket, false)
+        );
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn jis_backslash_position_is_not_the_right_bracket() {
+        assert_chars(get_jis_char, &[(Key::BackSlash, "]", "}")]);
+        assert_ne!(
+            get_jis_char(Key::BackSlash, false),
+            get_jis_char(Key::RightBracket, false)
+        );
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn jis_symbol_keys_by_virtual_key() {
+        assert_chars(
+            jis_symbol_char,
+            &[
+                (Key::BackQuote, "@", "`"),
+                (Key::LeftBracket, "[", "{"),
+                (Key::RightBracket, "]", "}"),
+                (Key::BackSlash, "¥", "|"),
+                (Key::SemiColon, ":", "*"),
+                (Key::Equal, ";", "+"),
+                (Key::Quote, "^", "~"),
+            ],
+        );
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn jis_symbol_keys_by_position() {
+        assert_chars(
+            jis_symbol_char,
+            &[
+                (Key::Equal, "^", "~"),
+                (Key::LeftBracket, "@", "`"),
+                (Key::RightBracket, "[", "{"),
+                (Key::BackSlash, "]", "}"),
+                (Key::SemiColon, ";", "+"),
+                (Key::Quote, ":", "*"),
+            ],
+        );
+        // Hankaku/Zenkaku types nothing, so Shift is never consumed
+        assert_eq!(
+            jis_symbol_char(Key::BackQuote, false),
+            Some(("Hankaku".to_string(), false))
+        );
+        assert_eq!(
+            jis_symbol_char(Key::BackQuote, true),
+            Some(("Hankaku".to_string(), false))
+        );
+    }
+
+    #[test]
+    fn jis_symbols_consume_shift() {
+        for key in [Key::Equal, Key::LeftBracket, Key::SemiColon, Key::Quote] {
+            assert_eq!(
+                jis_symbol_char(key, true).map(|(_, consumed)| consumed),
+                Some(true)
+            );
+        }
+    }
+
+    /// Fallback text for a JIS key with CapsLock applied, as the KeyPress branch does.
+    fn jis_with_caps(key: Key, shift: bool, caps_lock: bool) -> Option<String> {
+        layout_char(KeyboardLayout::Jis, key, shift)
+            .map(|(text, _)| apply_caps_lock(text, caps_lock))
+    }
+
+    #[test]
+    fn caps_lock_flips_letter_case() {
+        assert_eq!(jis_with_caps(Key::KeyA, false, false).as_deref(), Some("a"));
+        assert_eq!(jis_with_caps(Key::KeyA, true, false).as_deref(), Some("A"));
+        // Caps+letter
+        assert_eq!(jis_with_caps(Key::KeyA, false, true).as_deref(), Some("A"));
+        // Caps+Shift+letter
+        assert_eq!(jis_with_caps(Key::KeyA, true, true).as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn caps_lock_leaves_symbols_and_names_alone() {
+        assert_eq!(jis_with_caps(Key::Num1, true, true).as_deref(), Some("!"));
+        assert_eq!(jis_with_caps(Key::Num1, false, true).as_deref(), Some("1"));
+        assert_eq!(
+            jis_with_caps(Key::Return, false, true).as_deref(),
+            Some("Enter")
+        );
+    }
+
+    #[test]
+    fn caps_lock_state_follows_key_events() {
+        let mut caps_lock = false;
+        track_caps_lock(&mut caps_lock, &EventType::KeyPress(Key::CapsLock), false);
+        assert!(caps_lock);
+        // Auto-repeat while held doesn't toggle
+        track_caps_lock(&mut caps_lock, &EventType::KeyPress(Key::CapsLock), true);
+        assert!(caps_lock);
+        track_caps_lock(&mut caps_lock, &EventType::KeyRelease(Key::CapsLock), false);
+        if cfg!(target_os = "macos") {
+            // macOS reports the lock turning off as a release
+            assert!(!caps_lock);
+        } else {
+            assert!(caps_lock);
+            track_caps_lock(&mut caps_lock, &EventType::KeyPress(Key::CapsLock), false);
+            assert!(!caps_lock);
+        }
+    }
+
+    /// Labels of the payloads each of `releases` consecutive quick clicks emits.
+    fn click_sequence(releases: u32, suppress_click_on_doubleclick: bool) -> Vec<Vec<String>> {
+        let start = Instant::now();
+        let threshold = Duration::from_millis(300);
+        let mut last_click = None;
+        (0..releases)
+            .map(|i| {
+                let now = start + Duration::from_millis(100 * u64::from(i));
+                let count = click_count(last_click, Button::Left, now, threshold);
+                last_click = (count < 3).then_some((Button::Left, now, count));
+                click_payloads(count, suppress_click_on_doubleclick, "Left", 0)
+                    .into_iter()
+                    .map(|p| p.label)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn double_click_emits_both_clicks_by_default() {
+        assert_eq!(
+            click_sequence(2, false),
+            [
+                vec!["@Click[Left]"],
+                vec!["@Click[Left]", "@DoubleClick[Left]"]
+            ]
+        );
+    }
+
+    #[test]
+    fn double_click_can_suppress_the_second_click() {
+        assert_eq!(
+            click_sequence(3, true),
+            [
+                vec!["@Click[Left]"],
+                vec!["@DoubleClick[Left]"],
+                vec!["@TripleClick[Left]"]
+            ]
+        );
+    }
+
+    #[test]
+    fn click_runs_reset_after_a_triple_or_a_pause_or_another_button() {
+        // A fourth quick click starts over
+        assert_eq!(click_sequence(4, false)[3], vec!["@Click[Left]"]);
+
+        let start = Instant::now();
+        let threshold = Duration::from_millis(300);
+        let last = Some((Button::Left, start, 1));
+        assert_eq!(
+            click_count(last, Button::Left, start + threshold, threshold),
+            1
+        );
+        assert_eq!(click_count(last, Button::Right, start, threshold), 1);
+        assert_eq!(click_count(last, Button::Left, start, threshold), 2);
+    }
+
+    fn rect(x: f64, y: f64, width: f64, height: f64) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn percent_is_relative_to_the_monitor() {
+        let primary = rect(0.0, 0.0, 1920.0, 1080.0);
+        assert_eq!(percent_within(&primary, 960.0, 540.0), (50.0, 50.0));
+        assert_eq!(percent_within(&primary, 0.0, 1080.0), (0.0, 100.0));
+        let secondary = rect(1920.0, 0.0, 1000.0, 500.0);
+        assert_eq!(percent_within(&secondary, 2170.0, 125.0), (25.0, 25.0));
+    }
+
+    #[test]
+    fn percent_labels() {
+        let one = [rect(0.0, 0.0, 2000.0, 1000.0)];
+        let label = |monitors: &[Rect], x, y, decimals| {
+            mousemove_label(CoordinateDisplay::Percent, monitors, x, y, decimals, 0)
+        };
+        assert_eq!(label(&one, 900.0, 600.0, 0), "@MouseMove[45%, 60%]");
+        assert_eq!(label(&one, 901.0, 600.0, 2), "@MouseMove[45.05%, 60.00%]");
+
+        // With several monitors the one under the cursor is named
+        let two = [one[0], rect(2000.0, 0.0, 1000.0, 1000.0)];
+        assert_eq!(label(&two, 2500.0, 100.0, 0), "@MouseMove[mon:2 50%, 10%]");
+        // Off every monitor: global pixels
+        assert_eq!(label(&two, -10.0, 5.0, 0), "@MouseMove[-10, 5]");
+    }
+
+    #[test]
+    fn pixel_labels_ignore_monitors() {
+        let one = [rect(0.0, 0.0, 2000.0, 1000.0)];
+        assert_eq!(
+            mousemove_label(CoordinateDisplay::Pixels, &one, 900.4, 600.0, 0, 0),
+            "@MouseMove[900, 600]"
+        );
+    }
+
+    #[test]
+    fn overlapping_presses_form_a_chord_in_press_order() {
+        let mut held = Vec::new();
+        assert_eq!(press_button(&mut held, Button::Left), None);
+        assert_eq!(
+            press_button(&mut held, Button::Right).as_deref(),
+            Some("Left+Right")
+        );
+        // Releasing in either order keeps the other button held until the last goes up
+        assert!(release_button(&mut held, Button::Left));
+        assert!(!release_button(&mut held, Button::Right));
+
+        assert_eq!(press_button(&mut held, Button::Right), None);
+        assert_eq!(
+            press_button(&mut held, Button::Left).as_deref(),
+            Some("Right+Left")
+        );
+        assert!(release_button(&mut held, Button::Left));
+        assert!(!release_button(&mut held, Button::Right));
+        assert!(held.is_empty());
+    }
+
+    #[test]
+    fn chords_grow_with_each_extra_button() {
+        let mut held = Vec::new();
+        press_button(&mut held, Button::Left);
+        press_button(&mut held, Button::Right);
+        assert_eq!(
+            press_button(&mut held, Button::Middle).as_deref(),
+            Some("Left+Right+Middle")
+        );
+        assert!(release_button(&mut held, Button::Right));
+        // A button pressed again while held (a missed release) isn't counted twice
+        assert_eq!(
+            press_button(&mut held, Button::Left).as_deref(),
+            Some("Left+Middle")
+        );
+    }
+
+    #[test]
+    fn single_presses_never_chord() {
+        let mut held = Vec::new();
+        for _ in 0..3 {
+            assert_eq!(press_button(&mut held, Button::Left), None);
+            assert!(!release_button(&mut held, Button::Left));
+        }
+    }
+
+    #[test]
+    fn agreeing_names_are_not_a_conflict() {
+        assert_eq!(
+            table_disagreement(KeyboardLayout::Jis, Key::KeyA, false, false, "a"),
+            None
+        );
+        assert_eq!(
+            table_disagreement(KeyboardLayout::Jis, Key::KeyA, false, true, "A"),
+            None
+        );
+        // No table entry: nothing to disagree with
+        assert_eq!(
+            table_disagreement(KeyboardLayout::Jis, Key::F1, false, false, "F1"),
+            None
+        );
+    }
+
+    #[test]
+    fn disagreeing_names_resolve_per_policy() {
+        // The OS is on AZERTY, but the table is JIS: the Q position types `a`
+        let manual = table_disagreement(KeyboardLayout::Jis, Key::KeyQ, false, false, "a");
+        assert_eq!(manual, Some(("q".to_string(), true)));
+        let manual = manual.unwrap();
+        let os = ("a".to_string(), true);
+
+        assert_eq!(
+            resolve_name_conflict(NameConflictPolicy::Os, os.clone(), manual.clone()),
+            ("a".to_string(), true, false)
+        );
+        assert_eq!(
+            resolve_name_conflict(NameConflictPolicy::Manual, os.clone(), manual.clone()),
+            ("q".to_string(), true, false)
+        );
+        assert_eq!(
+            resolve_name_conflict(NameConflictPolicy::Both, os, manual),
+            ("a/q".to_string(), true, true)
+        );
+    }
+
+    #[test]
+    fn manual_name_decides_whether_shift_shows() {
+        // Shift+0 on JIS types nothing, so the table keeps Shift visible
+        let manual = table_disagreement(KeyboardLayout::Jis, Key::Num0, true, false, "=");
+        assert_eq!(manual, Some(("0".to_string(), false)));
+        assert_eq!(
+            resolve_name_conflict(
+                NameConflictPolicy::Manual,
+                ("=".to_string(), true),
+                manual.unwrap()
+            ),
+            ("0".to_string(), false, false)
+        );
+    }
+
+    #[test]
+    fn conflicts_are_counted_but_not_in_privacy_mode() {
+        let state = CaptureState::default();
+        state.record_name_conflict(Key::KeyQ, false, "a", "q");
+        state.record_name_conflict(Key::KeyQ, false, "a", "q");
+        {
+            let conflicts = state.name_conflicts.lock().unwrap();
+            let conflict = &conflicts[&(Key::KeyQ, false)];
+            assert_eq!((conflict.os_name.as_str(), conflict.count), ("a", 2));
+        }
+
+        state.update_settings(|s| s.privacy_mode = true);
+        state.record_name_conflict(Key::KeyW, false, "z", "w");
+        assert!(!state
+            .name_conflicts
+            .lock()
+            .unwrap()
+            .contains_key(&(Key::KeyW, false)));
+    }
+
+    /// Whether moving through `positions` with a button held from `start`
+    /// becomes a drag, accumulating travel as the MouseMove branch does.
+    fn drags(start: (f64, f64), positions: &[(f64, f64)], threshold: f64) -> bool {
+        let mut previous = start;
+        let mut path_px = 0.0;
+        positions.iter().any(|&pos| {
+            path_px += ((pos.0 - previous.0).powi(2) + (pos.1 - previous.1).powi(2)).sqrt();
+            previous = pos;
+            drag_threshold_crossed(start, pos, path_px, threshold)
+        })
+    }
+
+    #[test]
+    fn small_jitter_is_not_a_drag_at_a_raised_threshold() {
+        let start = (500.0, 500.0);
+        // A hand tremor: 5px out and back a few times during a click
+        let jitter = [
+            (505.0, 500.0),
+            (500.0, 500.0),
+            (500.0, 505.0),
+            (500.0, 500.0),
+        ];
+        assert!(drags(start, &jitter, 10.0));
+        assert!(!drags(start, &jitter, 50.0));
+    }
+
+    #[test]
+    fn moving_past_the_threshold_is_a_drag() {
+        let start = (0.0, 0.0);
+        assert!(!drags(start, &[(30.0, 40.0)], 50.0));
+        assert!(drags(start, &[(30.0, 41.0)], 50.0));
+        // A wide wiggle that ends back at the start still counts
+        assert!(drags(start, &[(30.0, 0.0), (0.0, 0.0)], 50.0));
+    }
+
+    #[test]
+    fn touch_input_can_use_its_own_threshold() {
+        let mut settings = Settings::default();
+        assert_eq!(drag_threshold_px(&settings, true), 10.0);
+        settings.drag_threshold_px = 50.0;
+        settings.touch_drag_threshold_px = Some(80.0);
+        assert_eq!(drag_threshold_px(&settings, false), 50.0);
+        assert_eq!(drag_threshold_px(&settings, true), 80.0);
+    }
+
+    #[test]
+    fn key_release_reports_time_since_the_first_press() {
+        let mut press_times = HashMap::new();
+        let pressed = Instant::now();
+        assert!(!record_key_press(&mut press_times, Key::KeyA, pressed));
+        // Auto-repeat doesn't restart the clock
+        assert!(record_key_press(
+            &mut press_times,
+            Key::KeyA,
+            pressed + Duration::from_millis(300)
+        ));
+
+        let released = pressed + Duration::from_millis(420);
+        let held_for = key_held_for(&mut press_times, Key::KeyA, released);
+        assert_eq!(held_for, Some(Duration::from_millis(420)));
+        assert_eq!(keyup_label("A", held_for.unwrap()), "@KeyUp[A 420ms]");
+        // The press was consumed by its release
+        assert_eq!(key_held_for(&mut press_times, Key::KeyA, released), None);
+    }
+
+    #[test]
+    fn overlapping_key_holds_are_timed_separately() {
+        let mut press_times = HashMap::new();
+        let start = Instant::now();
+        record_key_press(&mut press_times, Key::ShiftLeft, start);
+        record_key_press(
+            &mut press_times,
+            Key::KeyA,
+            start + Duration::from_millis(100),
+        );
+        let released = start + Duration::from_millis(250);
+        assert_eq!(
+            key_held_for(&mut press_times, Key::KeyA, released),
+            Some(Duration::from_millis(150))
+        );
+        assert_eq!(
+            key_held_for(&mut press_times, Key::ShiftLeft, released),
+            Some(Duration::from_millis(250))
+        );
+    }
+
+    #[test]
+    fn muted_keys_are_dropped_and_others_are_not() {
+        let settings = Settings {
+            muted_keys: ["F5", "CapsLock"]
+                .iter()
+                .map(|name| parse_key_name(name).unwrap())
+                .collect(),
+            ..Settings::default()
+        };
+
+        for key in [Key::F5, Key::CapsLock] {
+            assert!(is_muted(&settings, &EventType::KeyPress(key)), "{:?}", key);
+            assert!(
+                is_muted(&settings, &EventType::KeyRelease(key)),
+                "{:?}",
+                key
+            );
+        }
+        for key in [Key::F6, Key::KeyA, Key::Return] {
+            assert!(!is_muted(&settings, &EventType::KeyPress(key)), "{:?}", key);
+            assert!(
+                !is_muted(&settings, &EventType::KeyRelease(key)),
+                "{:?}",
+                key
+            );
+        }
+        assert!(!is_muted(&settings, &EventType::ButtonPress(Button::Left)));
+    }
+
+    #[test]
+    fn nothing_is_muted_by_default() {
+        let settings = Settings::default();
+        assert!(!is_muted(&settings, &EventType::KeyPress(Key::F5)));
+    }
+
+    #[test]
+    fn heatmap_counts_presses_per_key_while_enabled() {
+        let state = CaptureState::default();
+        state.set_armed(true);
+        count_heatmap_press(&state, Key::KeyA);
+        assert!(
+            state.key_heatmap.lock().unwrap().is_empty(),
+            "off by default"
+        );
+
+        state.update_settings(|s| s.key_heatmap = true);
+        for key in [Key::KeyA, Key::KeyA, Key::Space, Key::KeyA] {
+            count_heatmap_press(&state, key);
+        }
+        let heatmap = state.key_heatmap.lock().unwrap().clone();
+        assert_eq!(heatmap.get("A"), Some(&3));
+        assert_eq!(heatmap.get("Space"), Some(&1));
+        assert_eq!(heatmap.len(), 2);
+    }
+
+    #[test]
+    fn heatmap_ignores_presses_when_disarmed_or_private() {
+        let state = CaptureState::default();
+        state.update_settings(|s| s.key_heatmap = true);
+        state.set_armed(false);
+        count_heatmap_press(&state, Key::KeyA);
+
+        state.set_armed(true);
+        state.update_settings(|s| s.privacy_mode = true);
+        count_heatmap_press(&state, Key::KeyA);
+        assert!(state.key_heatmap.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn context_menu_key_is_named_menu() {
+        let menu = Key::Unknown(MENU_KEY_CODE);
+        assert_eq!(get_default_key_name(menu), "Menu");
+        assert_eq!(key_display_name(menu), "Menu");
+        assert_eq!(parse_key_name("menu"), Some(menu));
+        // Other unmapped codes keep their raw name
+        assert_eq!(key_display_name(Key::Unknown(0xFFFF)), "Unknown(65535)");
+    }
+
+    #[test]
+    fn modifier_hold_is_measured_per_modifier() {
+        let now = Instant::now();
+        let ago = |ms| now - Duration::from_millis(ms);
+        let press_times = HashMap::from([
+            (Key::ShiftLeft, ago(420)),
+            (Key::ShiftRight, ago(90)),
+            (Key::ControlLeft, ago(35)),
+            (Key::KeyS, ago(0)),
+        ]);
+        let hold_ms = modifier_hold_ms(&press_times, now);
+        let shift = Modifier::from_key(Key::ShiftLeft).unwrap().label();
+        let ctrl = Modifier::from_key(Key::ControlLeft).unwrap().label();
+        assert_eq!(hold_ms.get(shift), Some(&420));
+        assert_eq!(hold_ms.get(ctrl), Some(&35));
+        assert_eq!(hold_ms.len(), 2, "only modifiers are reported");
+    }
+
+    /// Event types left after `retain_in_roi` with the pointer at `pos`.
+    fn roi_survivors(settings: &Settings, pos: (f64, f64)) -> Vec<String> {
+        let mut payloads: Vec<InputEventPayload> = ["mousemove", "click", "scroll", "key"]
+            .into_iter()
+            .map(|event_type| InputEventPayload::new(event_type, event_type, 0))
+            .collect();
+        retain_in_roi(settings, Some(pos), &mut payloads);
+        payloads.into_iter().map(|p| p.event_type).collect()
+    }
+
+    #[test]
+    fn roi_drops_pointer_events_outside_region() {
+        let settings = Settings {
+            mouse_roi: Some(rect(100.0, 100.0, 200.0, 100.0)),
+            ..Settings::default()
+        };
+        assert_eq!(
+            roi_survivors(&settings, (150.0, 150.0)),
+            ["mousemove", "click", "scroll", "key"]
+        );
+        assert_eq!(roi_survivors(&settings, (50.0, 150.0)), ["key"]);
+        assert_eq!(roi_survivors(&settings, (150.0, 250.0)), ["key"]);
+
+        let moves_only = Settings {
+            roi_filters_clicks: false,
+            ..settings
+        };
+        assert_eq!(
+            roi_survivors(&moves_only, (50.0, 150.0)),
+            ["click", "scroll", "key"]
+        );
+    }
+
+    #[test]
+    fn no_roi_keeps_everything() {
+        assert_eq!(
+            roi_survivors(&Settings::default(), (-5000.0, 5000.0)),
+            ["mousemove", "click", "scroll", "key"]
+        );
+    }
+
+    #[test]
+    fn missed_modifier_release_is_cleared_after_timeout() {
+        let pressed_at = Instant::now();
+        let mut pressed_modifiers = HashSet::new();
+        let mut press_times = HashMap::new();
+        // Ctrl goes down, then its release never arrives
+        pressed_modifiers.insert(Key::ControlLeft);
+        record_key_press(&mut press_times, Key::ControlLeft, pressed_at);
+        record_key_press(&mut press_times, Key::KeyC, pressed_at);
+
+        let mut next_key_after = |ms, timeout_ms| {
+            clear_stuck_modifiers(
+                &mut pressed_modifiers,
+                &mut press_times,
+                Some(pressed_at),
+                pressed_at + Duration::from_millis(ms),
+                timeout_ms,
+            )
+        };
+        assert!(!next_key_after(999, 1000), "still within the timeout");
+        assert!(!next_key_after(5000, 0), "0 disables the reset");
+        assert!(next_key_after(1000, 1000));
+
+        assert!(pressed_modifiers.is_empty());
+        assert!(press_times.contains_key(&Key::KeyC));
+        assert!(!press_times.contains_key(&Key::ControlLeft));
+    }
+
+    #[test]
+    fn event_filter_covers_every_emitted_type_but_system() {
+        let allow_all = Settings::default();
+        assert!(passes_event_filter(&allow_all, "text"));
+
+        let settings = Settings {
+            event_filter: vec!["key".to_string(), "shortcut".to_string()],
+            ..Settings::default()
+        };
+        assert!(passes_event_filter(&settings, "key"));
+        assert!(passes_event_filter(&settings, "shortcut"));
+        for event_type in [
+            "text", "paste", "stats", "dwell", "idle", "pen", "caption", "click",
+        ] {
+            assert!(
+                !passes_event_filter(&settings, event_type),
+                "{}",
+                event_type
+            );
+        }
+        assert!(passes_event_filter(&settings, "system"));
+        assert!(passes_event_filter(&settings, "clear"));
+    }
+
+    #[test]
+    fn pointer_events_are_emitted_unless_filtered_out() {
+        assert!(emits_pointer_events(&Settings::default()));
+        let keys_only = Settings {
+            event_filter: vec!["key".to_string(), "shortcut".to_string()],
+            ..Settings::default()
+        };
+        assert!(!emits_pointer_events(&keys_only));
+        let pen_only = Settings {
+            event_filter: vec!["pen".to_string()],
+            ..Settings::default()
+        };
+        assert!(emits_pointer_events(&pen_only));
+    }
+
+    #[test]
+    fn hotkey_labels_follow_modifier_order_and_style() {
+        let hotkey = Hotkey::parse("Shift+Ctrl+T").unwrap();
+        let settings = Settings {
+            modifier_order: vec![
+                Modifier::Ctrl,
+                Modifier::Alt,
+                Modifier::Shift,
+                Modifier::Meta,
+            ],
+            ..Settings::default()
+        };
+        assert_eq!(hotkey_label(&hotkey, &settings), "Ctrl+Shift+T");
+        let symbols = Settings {
+            modifier_style: crate::state::ModifierStyle::MacSymbols,
+            ..settings
+        };
+        assert_eq!(hotkey_label(&hotkey, &symbols), "⌃+⇧+T");
+    }
+
+    #[test]
+    fn only_the_layouts_dead_keys_are_held_back() {
+        // Plain keys, which is all Wayland or `prefer_os_name: false` report
+        // without a name, are never mistaken for dead keys
+        for layout in KeyboardLayout::ALL {
+            let settings = Settings {
+                keyboard_layout: Some(*layout),
+                ..Settings::default()
+            };
+            for key in [Key::KeyE, Key::Num1, Key::Comma, Key::Space] {
+                assert_eq!(dead_key_mark(key, false, &settings), None);
+                assert_eq!(dead_key_mark(key, true, &settings), None);
+            }
+        }
+        // `^` is an ordinary key on US QWERTY and JIS
+        let us = Settings {
+            keyboard_layout: Some(KeyboardLayout::UsQwerty),
+            ..Settings::default()
+        };
+        assert_eq!(dead_key_mark(Key::Num6, true, &us), None);
+        assert_eq!(dead_key_mark(Key::Equal, false, &Settings::default()), None);
+        let azerty = Settings {
+            keyboard_layout: Some(KeyboardLayout::Azerty),
+            ..Settings::default()
+        };
+        assert_eq!(
+            dead_key_mark(Key::LeftBracket, false, &azerty).as_deref(),
+            Some("^")
+        );
+        assert_eq!(
+            dead_key_mark(Key::LeftBracket, true, &azerty).as_deref(),
+            Some("¨")
+        );
+    }
+
+    #[test]
+    fn dead_keys_compose_with_the_next_key() {
+        assert_eq!(compose_dead_key("^", "e"), "ê");
+        assert_eq!(compose_dead_key("^", "E"), "Ê");
+        assert_eq!(compose_dead_key("¨", "y"), "ÿ");
+        assert_eq!(compose_dead_key("^", "Space"), "^");
+        assert_eq!(compose_dead_key("^", "x"), "^x");
+        assert_eq!(compose_dead_key("¨", "Enter"), "Enter");
+    }
 }
@@ -1,7 +1,9 @@
+use std::sync::Arc;
 use tauri::{
     menu::{Menu, MenuItem},
     tray::TrayIconBuilder,
     Emitter, // Import Emitter trait for app.emit
+    Manager,
 };
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
@@ -10,20 +12,61 @@ fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
+mod cheatsheet;
 mod commands;
+mod config;
+mod dwell;
+mod event_server;
+mod hot_corner;
+mod idle;
 mod input_capture;
+mod logging;
+mod overlay_anchor;
+mod pen;
+mod pipeline;
+mod recording;
+mod schedule;
+mod shortcuts;
+mod state;
+mod stats;
+mod transcript;
+mod window_tracker;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .setup(|app| {
+            // The debug log is best-effort; capture must start even if it can't
+            match app.path().app_data_dir() {
+                Ok(dir) => {
+                    if let Err(e) = logging::init(dir.join("logs")) {
+                        eprintln!("Failed to start debug log: {}", e);
+                    }
+                }
+                Err(e) => eprintln!("Failed to resolve app data dir: {}", e),
+            }
+
+            let state = Arc::new(state::CaptureState::default());
+            config::load(app.handle(), &state);
+            app.manage(state.clone());
+
             let handle = app.handle().clone();
-            input_capture::start_capture(handle);
+            input_capture::start_capture(handle.clone(), state.clone());
+            schedule::start_schedule_watcher(handle.clone(), state.clone());
+            hot_corner::start_hot_corner_watcher(handle.clone(), state.clone());
+            dwell::start_dwell_watcher(handle.clone(), state.clone());
+            overlay_anchor::start_overlay_anchor_watcher(handle.clone(), state.clone());
+            stats::start_stats_watcher(handle.clone(), state.clone());
+            idle::start_idle_watcher(handle, state.clone());
+            window_tracker::start_window_tracker(state);
 
             // System Tray Setup
             let quit_i = MenuItem::with_id(app, "quit", "Quit EchoCast", true, None::<&str>)?;
             let settings_i = MenuItem::with_id(app, "settings", "Settings", true, None::<&str>)?;
-            let menu = Menu::with_items(app, &[&settings_i, &quit_i])?;
+            let pause_i =
+                MenuItem::with_id(app, "pause", "Pause/Resume Capture", true, None::<&str>)?;
+            let clear_i = MenuItem::with_id(app, "clear", "Clear Overlay", true, None::<&str>)?;
+            let menu = Menu::with_items(app, &[&settings_i, &pause_i, &clear_i, &quit_i])?;
 
             let _tray = TrayIconBuilder::with_id("tray")
                 .menu(&menu)
@@ -37,6 +80,14 @@ pub fn run() {
                             // Emit the same event as the keyboard shortcut
                             let _ = app.emit("toggle-settings", ());
                         }
+                        "pause" => {
+                            let state = app.state::<Arc<state::CaptureState>>();
+                            commands::set_paused_and_notify(app, &state, !state.is_paused());
+                        }
+                        "clear" => {
+                            let state = app.state::<Arc<state::CaptureState>>();
+                            commands::emit_clear(app, &state);
+                        }
                         _ => {}
                     }
                 })
@@ -53,8 +104,102 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             greet,
             commands::check_accessibility_permission,
+            commands::capture_health,
+            commands::capture_backend_status,
             commands::request_accessibility_permission,
-            commands::set_ignore_cursor_events
+            commands::set_ignore_cursor_events,
+            commands::set_overlay_size,
+            commands::set_overlay_anchor,
+            commands::toggle_overlay,
+            commands::set_hot_corner,
+            commands::set_auto_click_through,
+            commands::set_interactive_regions,
+            commands::set_ignore_cursor_events_region,
+            commands::set_coordinate_scale_override,
+            commands::set_coordinate_mode,
+            commands::set_coordinate_display,
+            commands::set_timestamp_mode,
+            commands::set_mouse_precision,
+            commands::set_mouse_grid,
+            commands::set_suppress_move_while_typing,
+            commands::set_mouse_roi,
+            commands::set_emit_key_release,
+            commands::set_key_hold_threshold,
+            commands::set_key_repeat_mode,
+            commands::set_show_lone_modifiers,
+            commands::set_distinguish_modifier_side,
+            commands::set_privacy_mode,
+            commands::set_label_format,
+            commands::set_stats_enabled,
+            commands::set_raw_passthrough,
+            commands::set_keyboard_layout,
+            commands::list_keyboard_layouts,
+            commands::set_drag_threshold,
+            commands::set_touch_drag_threshold,
+            commands::set_capture_schedule,
+            commands::set_muted_keys,
+            commands::get_prefer_os_name,
+            commands::set_prefer_os_name,
+            commands::set_name_conflict_policy,
+            commands::get_name_conflicts,
+            commands::reset_name_conflicts,
+            commands::set_modifier_order,
+            commands::set_modifier_style,
+            commands::set_include_delta,
+            commands::set_include_raw,
+            commands::set_startup_settling_ms,
+            commands::set_paste_threshold_ms,
+            commands::set_text_aggregation,
+            commands::set_key_heatmap_enabled,
+            commands::set_show_system_events,
+            commands::set_dwell,
+            commands::set_idle_timeout,
+            commands::set_event_filter,
+            commands::set_shortcut_only_mode,
+            commands::set_interesting_filter,
+            commands::get_key_heatmap,
+            commands::reset_key_heatmap,
+            commands::dump_debug_state,
+            commands::get_capture_state,
+            commands::get_pressed_state,
+            commands::set_pressed_state,
+            commands::set_stuck_modifier_timeout,
+            commands::set_mousemove_throttle,
+            commands::set_double_click_threshold,
+            commands::set_suppress_click_on_doubleclick,
+            commands::emit_pen_event,
+            commands::emit_test_event,
+            commands::set_pause_hotkey,
+            commands::set_panic_hotkey,
+            commands::set_clear_hotkey,
+            commands::clear_overlay,
+            commands::set_capture_paused,
+            commands::arm_capture,
+            commands::disarm_capture,
+            commands::stop_capture,
+            commands::start_capture_cmd,
+            commands::set_scroll_milestone,
+            commands::set_ttl_by_type,
+            commands::set_scroll_gesture_gap_ms,
+            commands::set_scroll_step,
+            commands::set_scroll_modifiers,
+            commands::set_shortcut_labels,
+            commands::preview_shortcuts,
+            commands::cancel_preview_shortcuts,
+            commands::set_log_level,
+            commands::get_log_path,
+            commands::measure_emit_latency,
+            commands::get_recent_events,
+            commands::export_transcript,
+            commands::export_cheatsheet,
+            commands::set_recording_indicator_color,
+            commands::start_recording,
+            commands::stop_recording,
+            commands::replay_recording,
+            commands::start_event_server,
+            commands::stop_event_server,
+            commands::start_recording_with_countdown,
+            commands::cancel_recording_countdown
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,117 @@
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+
+const LOG_FILE_NAME: &str = "echocast.log";
+/// Size at which the active log is rotated
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+/// Number of rotated files kept next to the active one (echocast.1.log is the newest)
+const MAX_ROTATED_FILES: usize = 3;
+
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+static LOG_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Formats records on the calling thread and hands the line to a writer
+/// thread, so logging from the capture callback never waits on disk I/O.
+struct FileLogger {
+    sender: Mutex<Sender<String>>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!(
+            "{} [{}] {}: {}\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        // A closed channel means the writer died; dropping the line is all we can do
+        let _ = self.sender.lock().unwrap().send(line);
+    }
+
+    fn flush(&self) {}
+}
+
+fn rotated_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("echocast.{}.log", index))
+}
+
+/// Shifts echocast.log -> echocast.1.log -> ... dropping the oldest file.
+fn rotate(dir: &Path) {
+    let _ = fs::remove_file(rotated_path(dir, MAX_ROTATED_FILES));
+    for index in (1..MAX_ROTATED_FILES).rev() {
+        let _ = fs::rename(rotated_path(dir, index), rotated_path(dir, index + 1));
+    }
+    let _ = fs::rename(dir.join(LOG_FILE_NAME), rotated_path(dir, 1));
+}
+
+fn open_log(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn run_writer(dir: PathBuf, mut file: File, receiver: mpsc::Receiver<String>) {
+    let path = dir.join(LOG_FILE_NAME);
+    let mut written = file.metadata().map(|m| m.len()).unwrap_or(0);
+
+    for line in receiver {
+        if written + line.len() as u64 > MAX_LOG_BYTES {
+            rotate(&dir);
+            match open_log(&path) {
+                Ok(new_file) => {
+                    file = new_file;
+                    written = 0;
+                }
+                Err(e) => {
+                    eprintln!("Failed to reopen log file: {}", e);
+                    return;
+                }
+            }
+        }
+        if file.write_all(line.as_bytes()).is_ok() {
+            written += line.len() as u64;
+        }
+    }
+}
+
+/// Installs the global file logger writing to `dir/echocast.log`.
+pub fn init(dir: PathBuf) -> Result<(), String> {
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join(LOG_FILE_NAME);
+    let file = open_log(&path).map_err(|e| e.to_string())?;
+
+    let (sender, receiver) = mpsc::channel();
+    log::set_boxed_logger(Box::new(FileLogger {
+        sender: Mutex::new(sender),
+    }))
+    .map_err(|e| e.to_string())?;
+    log::set_max_level(DEFAULT_LEVEL);
+    let _ = LOG_PATH.set(path);
+
+    thread::spawn(move || run_writer(dir, file, receiver));
+    Ok(())
+}
+
+/// Path of the active log file, if the logger was initialised.
+pub fn log_path() -> Option<&'static Path> {
+    LOG_PATH.get().map(PathBuf::as_path)
+}
+
+/// Parses "off", "error", "warn", "info", "debug" or "trace" (case-insensitive).
+pub fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    level
+        .parse::<LevelFilter>()
+        .map_err(|_| format!("Unknown log level: {}", level))
+}
@@ -0,0 +1,134 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, LogicalPosition, Manager, PhysicalPosition, Position, WebviewWindow};
+
+use crate::hot_corner::MONITOR_REFRESH;
+use crate::input_capture::{monitor_rect, window_rect_to_screen};
+use crate::state::{CaptureState, OverlayAnchor, Rect};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Minimum time between window moves, so a fast-moving cursor doesn't thrash the window.
+const MOVE_DEBOUNCE: Duration = Duration::from_millis(150);
+/// Gap between the cursor and the overlay's top-left corner in `follow_cursor` mode.
+const CURSOR_OFFSET: f64 = 24.0;
+
+/// Overlay frame in rdev screen coordinates.
+fn window_frame(window: &WebviewWindow) -> Option<Rect> {
+    let origin = window.outer_position().ok()?;
+    let size = window.outer_size().ok()?;
+    let scale = window.scale_factor().ok()?;
+    let css_size = Rect {
+        x: 0.0,
+        y: 0.0,
+        width: size.width as f64 / scale,
+        height: size.height as f64 / scale,
+    };
+    Some(window_rect_to_screen(css_size, origin, scale))
+}
+
+/// Keeps a `width` x `height` window at `(x, y)` inside `bounds` where it fits.
+fn clamp_into(bounds: &Rect, x: f64, y: f64, width: f64, height: f64) -> (f64, f64) {
+    let max_x = (bounds.x + bounds.width - width).max(bounds.x);
+    let max_y = (bounds.y + bounds.height - height).max(bounds.y);
+    (x.clamp(bounds.x, max_x), y.clamp(bounds.y, max_y))
+}
+
+/// Moves the overlay so its top-left corner is at `(x, y)` in rdev screen coordinates.
+fn move_window(window: &WebviewWindow, x: f64, y: f64) {
+    let position = if cfg!(target_os = "macos") {
+        Position::Logical(LogicalPosition::new(x, y))
+    } else {
+        Position::Physical(PhysicalPosition::new(x.round() as i32, y.round() as i32))
+    };
+    if let Err(e) = window.set_position(position) {
+        log::warn!("Failed to move overlay: {}", e);
+    }
+}
+
+/// Repositions the overlay according to `overlay_anchor`: next to the cursor
+/// (`follow_cursor`), or onto the cursor's monitor at the same relative spot
+/// (`follow_monitor`). Nothing moves in `fixed` mode.
+pub fn start_overlay_anchor_watcher(app: AppHandle, state: Arc<CaptureState>) {
+    thread::spawn(move || {
+        let mut monitors: Vec<Rect> = Vec::new();
+        let mut monitors_fetched: Option<Instant> = None;
+        let mut last_cursor: Option<(f64, f64)> = None;
+        let mut last_move: Option<Instant> = None;
+
+        loop {
+            thread::sleep(POLL_INTERVAL);
+
+            let settings = state.settings();
+            if settings.overlay_anchor == OverlayAnchor::Fixed {
+                last_cursor = None;
+                continue;
+            }
+            if last_move.is_some_and(|t| t.elapsed() < MOVE_DEBOUNCE) {
+                continue;
+            }
+            let Some((x, y)) = *state.last_mouse_pos.lock().unwrap() else {
+                continue;
+            };
+            if last_cursor == Some((x, y)) {
+                continue;
+            }
+            last_cursor = Some((x, y));
+
+            if monitors_fetched.is_none_or(|t| t.elapsed() >= MONITOR_REFRESH) {
+                if let Ok(list) = app.available_monitors() {
+                    monitors = list
+                        .iter()
+                        .map(|m| monitor_rect(m, settings.coordinate_scale_override))
+                        .collect();
+                }
+                monitors_fetched = Some(Instant::now());
+            }
+            let Some(target_monitor) = monitors.iter().find(|m| m.contains(x, y)) else {
+                continue;
+            };
+            let Some(window) = app.get_webview_window("main") else {
+                continue;
+            };
+            let Some(frame) = window_frame(&window) else {
+                continue;
+            };
+
+            let target = match settings.overlay_anchor {
+                OverlayAnchor::Fixed => continue,
+                OverlayAnchor::FollowCursor => clamp_into(
+                    target_monitor,
+                    x + CURSOR_OFFSET,
+                    y + CURSOR_OFFSET,
+                    frame.width,
+                    frame.height,
+                ),
+                OverlayAnchor::FollowMonitor => {
+                    let current = monitors.iter().find(|m| m.contains(frame.x, frame.y));
+                    match current {
+                        // Already on the cursor's monitor
+                        Some(m) if m.contains(x, y) => continue,
+                        Some(m) => clamp_into(
+                            target_monitor,
+                            target_monitor.x + (frame.x - m.x),
+                            target_monitor.y + (frame.y - m.y),
+                            frame.width,
+                            frame.height,
+                        ),
+                        None => clamp_into(
+                            target_monitor,
+                            target_monitor.x,
+                            target_monitor.y,
+                            frame.width,
+                            frame.height,
+                        ),
+                    }
+                }
+            };
+            if (target.0 - frame.x).abs() >= 1.0 || (target.1 - frame.y).abs() >= 1.0 {
+                move_window(&window, target.0, target.1);
+                last_move = Some(Instant::now());
+            }
+        }
+    });
+}
@@ -0,0 +1,68 @@
+use tauri::AppHandle;
+
+use crate::input_capture::{emit_event, InputEventPayload};
+use crate::state::CaptureState;
+
+/// One pen reading as reported by an external tablet integration. rdev only
+/// reports mouse, keyboard and wheel events: pressure and tilt arrive as
+/// `WM_POINTER` messages on Windows, `NSEvent` tablet fields on macOS and
+/// XInput2/libinput tablet devices on Linux, none of which reach its hooks.
+/// Pen input is therefore fed in from outside (e.g. a tablet driver bridge)
+/// via `emit_pen_event`.
+#[derive(Clone, Copy, serde::Deserialize)]
+pub struct PenSample {
+    /// Tip pressure, 0.0 (hovering) to 1.0 (full pressure).
+    pub pressure: f64,
+    /// Tilt along each axis in degrees, -90 to 90, if the tablet reports it.
+    pub tilt_x: Option<f64>,
+    pub tilt_y: Option<f64>,
+    /// Pen position in rdev screen coordinates; the last known cursor
+    /// position is used when omitted.
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+}
+
+fn validate(sample: &PenSample) -> Result<(), String> {
+    if !(0.0..=1.0).contains(&sample.pressure) {
+        return Err(format!(
+            "Pen pressure must be between 0 and 1: {}",
+            sample.pressure
+        ));
+    }
+    for tilt in [sample.tilt_x, sample.tilt_y].into_iter().flatten() {
+        if !(-90.0..=90.0).contains(&tilt) {
+            return Err(format!(
+                "Pen tilt must be between -90 and 90 degrees: {}",
+                tilt
+            ));
+        }
+    }
+    if sample.x.is_some() != sample.y.is_some() {
+        return Err("Pen position needs both x and y".to_string());
+    }
+    Ok(())
+}
+
+/// Emits `@Pen[0.75]` for a sample, following the same pause and arming
+/// rules as captured input.
+pub(crate) fn emit_pen(
+    app: &AppHandle,
+    state: &CaptureState,
+    sample: PenSample,
+) -> Result<(), String> {
+    validate(&sample)?;
+    if !state.is_armed() || state.is_paused() {
+        return Ok(());
+    }
+    let position = match (sample.x, sample.y) {
+        (Some(x), Some(y)) => Some((x, y)),
+        _ => *state.last_mouse_pos.lock().unwrap(),
+    };
+    let mut payload = InputEventPayload::now("pen", format!("@Pen[{:.2}]", sample.pressure))
+        .with_pen(sample.pressure, sample.tilt_x, sample.tilt_y);
+    if let Some(position) = position {
+        payload = payload.at_position(position, *state.focused_window.lock().unwrap());
+    }
+    emit_event(app, state, payload);
+    Ok(())
+}
@@ -0,0 +1,561 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+use crate::input_capture::{emit_event, InputEventPayload};
+use crate::state::{CaptureState, Settings};
+
+/// Minimum run of rapid keystrokes reported as a paste; shorter runs are
+/// ordinary key rollover.
+const PASTE_MIN_KEYS: usize = 3;
+
+/// Typing pause after which aggregated text is shown.
+const TEXT_IDLE_GAP: Duration = Duration::from_secs(1);
+
+/// Payloads waiting for the pipeline thread. Beyond this, queued moves are
+/// dropped first so the capture callback never blocks.
+const QUEUE_CAPACITY: usize = 1024;
+
+/// How a captured payload takes part in stream-level coalescing.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum KeyRole {
+    /// Plain keystroke that produced text
+    Text,
+    /// `keyup` payload, folded into a paste together with its key press
+    Release,
+    /// Key pressed with Ctrl, Alt or Meta held
+    Shortcut,
+    Other,
+}
+
+/// A payload produced by the capture callback, on its way to the overlay.
+pub(crate) struct CapturedEvent {
+    pub payload: InputEventPayload,
+    /// When the OS reported the underlying input
+    pub captured_at: Instant,
+    pub role: KeyRole,
+}
+
+struct Queue {
+    events: Mutex<VecDeque<CapturedEvent>>,
+    ready: Condvar,
+    senders: AtomicUsize,
+}
+
+/// Bounded, never-blocking handle for feeding the pipeline. When the queue is
+/// full the oldest `mousemove` makes room (the oldest event if there is none),
+/// and the drop is counted in `CaptureState::events_dropped`.
+pub(crate) struct PipelineSender {
+    queue: Arc<Queue>,
+    state: Arc<CaptureState>,
+}
+
+impl PipelineSender {
+    pub(crate) fn send(&self, event: CapturedEvent) {
+        let mut events = self.queue.events.lock().unwrap();
+        if events.len() >= QUEUE_CAPACITY {
+            let victim = events
+                .iter()
+                .position(|e| e.payload.event_type == "mousemove")
+                .unwrap_or(0);
+            if let Some(dropped) = events.remove(victim) {
+                self.state.events_dropped.fetch_add(1, Ordering::Relaxed);
+                log::trace!(
+                    "Pipeline full, dropped {} event",
+                    dropped.payload.event_type
+                );
+            }
+        }
+        events.push_back(event);
+        self.queue.ready.notify_one();
+    }
+}
+
+impl Clone for PipelineSender {
+    fn clone(&self) -> Self {
+        self.queue.senders.fetch_add(1, Ordering::SeqCst);
+        Self {
+            queue: self.queue.clone(),
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl Drop for PipelineSender {
+    fn drop(&mut self) {
+        self.queue.senders.fetch_sub(1, Ordering::SeqCst);
+        self.queue.ready.notify_all();
+    }
+}
+
+impl Queue {
+    /// Next event, waiting until `deadline` (or indefinitely). Disconnected
+    /// once the queue is empty and every sender is gone.
+    fn recv(&self, deadline: Option<Instant>) -> Result<CapturedEvent, RecvTimeoutError> {
+        let mut events = self.events.lock().unwrap();
+        loop {
+            if let Some(event) = events.pop_front() {
+                return Ok(event);
+            }
+            if self.senders.load(Ordering::SeqCst) == 0 {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+            events = match deadline {
+                None => self.ready.wait(events).unwrap(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(RecvTimeoutError::Timeout);
+                    }
+                    self.ready.wait_timeout(events, deadline - now).unwrap().0
+                }
+            };
+        }
+    }
+}
+
+/// Collapses keystrokes arriving faster than a human can type (a paste or a
+/// macro injecting input) into a single `@Paste[N chars]` event.
+#[derive(Default)]
+struct PasteDetector {
+    /// The current run: text keys plus the releases that arrived during it
+    pending: Vec<CapturedEvent>,
+    last_text_at: Option<Instant>,
+    /// When the run is closed if no further key arrives
+    deadline: Option<Instant>,
+}
+
+impl PasteDetector {
+    fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    fn push(&mut self, event: CapturedEvent, threshold: Duration, out: &mut Vec<CapturedEvent>) {
+        if threshold.is_zero() {
+            self.flush(out);
+            out.push(event);
+            return;
+        }
+        match event.role {
+            KeyRole::Text => {
+                let continues_run = self.last_text_at.is_some_and(|last| {
+                    event.captured_at.saturating_duration_since(last) < threshold
+                });
+                if !continues_run {
+                    self.flush(out);
+                }
+                self.last_text_at = Some(event.captured_at);
+                self.deadline = Some(Instant::now() + threshold);
+                self.pending.push(event);
+            }
+            KeyRole::Release if !self.pending.is_empty() => self.pending.push(event),
+            _ => {
+                self.flush(out);
+                out.push(event);
+            }
+        }
+    }
+
+    /// Ends the run once no further key arrived before its deadline.
+    fn flush_if_idle(&mut self, now: Instant, out: &mut Vec<CapturedEvent>) {
+        if self.deadline.is_some_and(|deadline| now >= deadline) {
+            self.flush(out);
+        }
+    }
+
+    fn flush(&mut self, out: &mut Vec<CapturedEvent>) {
+        self.last_text_at = None;
+        self.deadline = None;
+        let chars = self
+            .pending
+            .iter()
+            .filter(|e| e.role == KeyRole::Text)
+            .count();
+        if chars < PASTE_MIN_KEYS {
+            out.append(&mut self.pending);
+            return;
+        }
+        let first = self.pending.remove(0);
+        self.pending.clear();
+        out.push(CapturedEvent {
+            payload: InputEventPayload::new(
+                "paste",
+                format!("@Paste[{} chars]", chars),
+                first.payload.timestamp,
+            ),
+            captured_at: first.captured_at,
+            role: KeyRole::Other,
+        });
+    }
+}
+
+/// Buffers printable keystrokes and shows them as one `@Type[hello]` event
+/// after a typing pause, or right before any other key or pointer action.
+/// Backspace takes back the last buffered character.
+#[derive(Default)]
+struct TextAggregator {
+    text: String,
+    /// Timestamp and capture time of the first buffered keystroke
+    first: Option<(u128, Instant)>,
+    deadline: Option<Instant>,
+}
+
+impl TextAggregator {
+    fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    fn push(&mut self, event: CapturedEvent, enabled: bool, out: &mut Vec<CapturedEvent>) {
+        if !enabled {
+            self.flush(out);
+            out.push(event);
+            return;
+        }
+        if let Some(c) = typed_char(&event) {
+            self.first
+                .get_or_insert((event.payload.timestamp, event.captured_at));
+            self.text.push(c);
+            self.deadline = Some(Instant::now() + TEXT_IDLE_GAP);
+            return;
+        }
+        if self.text.is_empty() {
+            out.push(event);
+            return;
+        }
+        if event.role == KeyRole::Release {
+            // Releases of the buffered keys are part of the typing
+        } else if event.payload.label == "@Key[Backspace]" {
+            self.text.pop();
+            if self.text.is_empty() {
+                self.first = None;
+                self.deadline = None;
+            } else {
+                self.deadline = Some(Instant::now() + TEXT_IDLE_GAP);
+            }
+        } else if event.payload.event_type == "mousemove" {
+            // Moving the pointer doesn't end a word
+            out.push(event);
+        } else {
+            self.flush(out);
+            out.push(event);
+        }
+    }
+
+    fn flush_if_idle(&mut self, now: Instant, out: &mut Vec<CapturedEvent>) {
+        if self.deadline.is_some_and(|deadline| now >= deadline) {
+            self.flush(out);
+        }
+    }
+
+    fn flush(&mut self, out: &mut Vec<CapturedEvent>) {
+        self.deadline = None;
+        let Some((timestamp, captured_at)) = self.first.take() else {
+            return;
+        };
+        let text = std::mem::take(&mut self.text);
+        out.push(CapturedEvent {
+            payload: InputEventPayload::new("text", format!("@Type[{}]", text), timestamp),
+            captured_at,
+            role: KeyRole::Other,
+        });
+    }
+}
+
+/// The character a plain text keystroke typed; `None` for Enter and Tab,
+/// which end a word rather than being part of it.
+fn typed_char(event: &CapturedEvent) -> Option<char> {
+    if event.role != KeyRole::Text || event.payload.event_type != "key" {
+        return None;
+    }
+    let value = event
+        .payload
+        .label
+        .strip_prefix("@Key[")?
+        .strip_suffix(']')?;
+    if value == "Space" {
+        return Some(' ');
+    }
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+/// Limits `mousemove` payloads to one per interval. The newest suppressed
+/// position is held back and emitted when the interval ends (or right before
+/// the next other event), so the overlay always ends on the real position.
+#[derive(Default)]
+struct MoveThrottle {
+    pending: Option<CapturedEvent>,
+    last_emit: Option<Instant>,
+    interval: Duration,
+}
+
+impl MoveThrottle {
+    fn deadline(&self) -> Option<Instant> {
+        self.pending.as_ref()?;
+        self.last_emit.map(|last| last + self.interval)
+    }
+
+    fn push(&mut self, event: CapturedEvent, interval: Duration, out: &mut Vec<CapturedEvent>) {
+        self.interval = interval;
+        if event.payload.event_type != "mousemove" {
+            self.flush(out);
+            out.push(event);
+            return;
+        }
+        let now = Instant::now();
+        if self
+            .last_emit
+            .is_none_or(|last| now.duration_since(last) >= interval)
+        {
+            self.pending = None;
+            self.last_emit = Some(now);
+            out.push(event);
+        } else {
+            self.pending = Some(event);
+        }
+    }
+
+    fn flush_if_due(&mut self, now: Instant, out: &mut Vec<CapturedEvent>) {
+        if self.deadline().is_some_and(|deadline| now >= deadline) {
+            self.flush(out);
+        }
+    }
+
+    fn flush(&mut self, out: &mut Vec<CapturedEvent>) {
+        if let Some(event) = self.pending.take() {
+            self.last_emit = Some(Instant::now());
+            out.push(event);
+        }
+    }
+}
+
+/// "Interesting events only" mode: plain input is dropped until a trigger
+/// (a shortcut, or an event type listed in `interesting_triggers`) occurs,
+/// after which everything passes for `interesting_window_ms`.
+#[derive(Default)]
+struct InterestFilter {
+    open_until: Option<Instant>,
+}
+
+impl InterestFilter {
+    fn admit(&mut self, event: &CapturedEvent, settings: &Settings) -> bool {
+        if !settings.interesting_only
+            || settings.raw_passthrough
+            || event.payload.event_type == "system"
+        {
+            return true;
+        }
+        let is_trigger = settings.interesting_triggers.iter().any(|trigger| {
+            if trigger == "shortcut" {
+                event.role == KeyRole::Shortcut
+            } else {
+                *trigger == event.payload.event_type
+            }
+        });
+        let now = Instant::now();
+        if is_trigger {
+            self.open_until = Some(now + Duration::from_millis(settings.interesting_window_ms));
+            return true;
+        }
+        self.open_until.is_some_and(|until| now < until)
+    }
+}
+
+/// Starts the thread that turns captured payloads into `input-event` emits.
+/// Runs until every sender is dropped.
+pub(crate) fn start_pipeline(app: AppHandle, state: Arc<CaptureState>) -> PipelineSender {
+    let queue = Arc::new(Queue {
+        events: Mutex::new(VecDeque::with_capacity(QUEUE_CAPACITY)),
+        ready: Condvar::new(),
+        senders: AtomicUsize::new(1),
+    });
+    let sender = PipelineSender {
+        queue: queue.clone(),
+        state: state.clone(),
+    };
+    thread::spawn(move || {
+        let mut paste = PasteDetector::default();
+        let mut text = TextAggregator::default();
+        let mut throttle = MoveThrottle::default();
+        let mut interest = InterestFilter::default();
+        let mut staged = Vec::new();
+        let mut typed = Vec::new();
+        let mut out = Vec::new();
+        loop {
+            let settings = state.settings();
+            let threshold = Duration::from_millis(settings.paste_threshold_ms);
+            // Raw passthrough forwards every move
+            let move_interval = if settings.raw_passthrough {
+                Duration::ZERO
+            } else {
+                Duration::from_millis(settings.mousemove_throttle_ms)
+            };
+            let aggregate_text = settings.text_aggregation && !settings.raw_passthrough;
+            // Block indefinitely unless a run, buffered text or a held-back move is waiting
+            let deadline = [paste.deadline(), text.deadline(), throttle.deadline()]
+                .into_iter()
+                .flatten()
+                .min();
+            let disconnected = match queue.recv(deadline) {
+                Ok(event) => {
+                    paste.push(event, threshold, &mut staged);
+                    false
+                }
+                Err(RecvTimeoutError::Timeout) => false,
+                Err(RecvTimeoutError::Disconnected) => true,
+            };
+            if disconnected {
+                paste.flush(&mut staged);
+            } else {
+                paste.flush_if_idle(Instant::now(), &mut staged);
+            }
+            for event in staged.drain(..) {
+                text.push(event, aggregate_text, &mut typed);
+            }
+            if disconnected {
+                text.flush(&mut typed);
+            } else {
+                text.flush_if_idle(Instant::now(), &mut typed);
+            }
+            for event in typed.drain(..) {
+                throttle.push(event, move_interval, &mut out);
+            }
+            if disconnected {
+                throttle.flush(&mut out);
+            } else {
+                throttle.flush_if_due(Instant::now(), &mut out);
+            }
+
+            let settings = state.settings();
+            for event in out.drain(..) {
+                // Events still queued when capture was disarmed (e.g. by the panic hotkey) are dropped
+                if !state.is_armed() && event.payload.event_type != "system" {
+                    continue;
+                }
+                // Text only reaches here if typed before shortcut-only mode was switched on
+                if settings.shortcut_only
+                    && matches!(event.payload.event_type.as_str(), "text" | "paste")
+                {
+                    log::trace!(
+                        "Dropped {} event: shortcut-only mode",
+                        event.payload.event_type
+                    );
+                    continue;
+                }
+                if !interest.admit(&event, &settings) {
+                    log::trace!(
+                        "Dropped {} event: not interesting",
+                        event.payload.event_type
+                    );
+                    continue;
+                }
+                emit_event(&app, &state, event.payload);
+                state.record_emit_latency(event.captured_at.elapsed());
+            }
+            if disconnected {
+                break;
+            }
+        }
+    });
+    sender
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLD: Duration = Duration::from_millis(5);
+
+    fn key(label: &str, captured_at: Instant) -> CapturedEvent {
+        CapturedEvent {
+            payload: InputEventPayload::new("key", format!("@Key[{}]", label), 0),
+            captured_at,
+            role: KeyRole::Text,
+        }
+    }
+
+    fn labels(events: &[CapturedEvent]) -> Vec<&str> {
+        events.iter().map(|e| e.payload.label.as_str()).collect()
+    }
+
+    #[test]
+    fn sub_millisecond_keys_become_one_paste() {
+        let mut paste = PasteDetector::default();
+        let mut out = Vec::new();
+        let start = Instant::now();
+        for (i, c) in "hello".chars().enumerate() {
+            let at = start + Duration::from_micros(200 * i as u64);
+            paste.push(key(&c.to_string(), at), THRESHOLD, &mut out);
+        }
+        assert!(out.is_empty(), "the run is held back until it ends");
+        paste.flush_if_idle(Instant::now() + THRESHOLD, &mut out);
+        assert_eq!(labels(&out), ["@Paste[5 chars]"]);
+        assert_eq!(out[0].payload.event_type, "paste");
+    }
+
+    #[test]
+    fn human_typing_passes_through() {
+        let mut paste = PasteDetector::default();
+        let mut out = Vec::new();
+        let start = Instant::now();
+        for (i, c) in "hey".chars().enumerate() {
+            let at = start + Duration::from_millis(120 * i as u64);
+            paste.push(key(&c.to_string(), at), THRESHOLD, &mut out);
+        }
+        paste.flush_if_idle(Instant::now() + THRESHOLD, &mut out);
+        assert_eq!(labels(&out), ["@Key[h]", "@Key[e]", "@Key[y]"]);
+    }
+
+    #[test]
+    fn short_rollover_is_not_a_paste() {
+        let mut paste = PasteDetector::default();
+        let mut out = Vec::new();
+        let start = Instant::now();
+        paste.push(key("a", start), THRESHOLD, &mut out);
+        paste.push(
+            key("s", start + Duration::from_micros(300)),
+            THRESHOLD,
+            &mut out,
+        );
+        paste.flush_if_idle(Instant::now() + THRESHOLD, &mut out);
+        assert_eq!(labels(&out), ["@Key[a]", "@Key[s]"]);
+    }
+
+    #[test]
+    fn other_events_end_the_run_and_zero_threshold_disables_detection() {
+        let mut paste = PasteDetector::default();
+        let mut out = Vec::new();
+        let start = Instant::now();
+        for i in 0..3 {
+            paste.push(
+                key("x", start + Duration::from_micros(100 * i)),
+                THRESHOLD,
+                &mut out,
+            );
+        }
+        let click = CapturedEvent {
+            payload: InputEventPayload::new("click", "@Click[Left]", 0),
+            captured_at: start,
+            role: KeyRole::Other,
+        };
+        paste.push(click, THRESHOLD, &mut out);
+        assert_eq!(labels(&out), ["@Paste[3 chars]", "@Click[Left]"]);
+
+        let mut out = Vec::new();
+        for i in 0..3 {
+            paste.push(
+                key("x", start + Duration::from_micros(100 * i)),
+                Duration::ZERO,
+                &mut out,
+            );
+        }
+        assert_eq!(labels(&out), ["@Key[x]", "@Key[x]", "@Key[x]"]);
+    }
+}
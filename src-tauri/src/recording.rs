@@ -0,0 +1,153 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, LineWriter, Write};
+use std::sync::atomic::Ordering;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::input_capture::{emit_system, get_timestamp, InputEventPayload};
+use crate::state::CaptureState;
+
+#[derive(Clone, serde::Serialize)]
+struct RecordingStatePayload {
+    active: bool,
+    color: String,
+}
+
+/// Tells the overlay whether a recording is running and which indicator color
+/// to show. Sent on every start/stop and whenever the color changes.
+pub(crate) fn emit_recording_state(app: &AppHandle, state: &CaptureState) {
+    let payload = RecordingStatePayload {
+        active: state.is_recording(),
+        color: state.settings().recording_indicator_color.clone(),
+    };
+    let _ = app.emit("recording-state", payload);
+}
+
+/// Creates (or truncates) the recording file up front, so a bad path is
+/// reported to the caller rather than from the writer thread.
+pub(crate) fn open_recording_file(path: &str) -> Result<File, String> {
+    File::create(path).map_err(|e| format!("Cannot create {}: {}", path, e))
+}
+
+/// Starts writing every emitted payload to `file`, one JSON object per line
+/// (each carrying its own `timestamp`), so a recording can be streamed back.
+/// Lines are written on a separate thread so emitting never waits on disk.
+pub(crate) fn start(app: &AppHandle, state: &CaptureState, file: File) -> Result<(), String> {
+    let mut recorder = state.recorder.lock().unwrap();
+    if recorder.is_some() {
+        return Err("A recording is already running".to_string());
+    }
+    let (sender, receiver) = mpsc::channel::<InputEventPayload>();
+    thread::spawn(move || {
+        let mut writer = LineWriter::new(file);
+        for payload in receiver {
+            let Ok(line) = serde_json::to_string(&payload) else {
+                continue;
+            };
+            if let Err(e) = writeln!(writer, "{}", line) {
+                log::error!("Recording write failed, stopping writer: {}", e);
+                return;
+            }
+        }
+    });
+    *recorder = Some(sender);
+    drop(recorder);
+
+    state.set_recording(true);
+    log::info!("Recording started");
+    emit_recording_state(app, state);
+    Ok(())
+}
+
+/// Streams the events of a recording written by `start` to `visit`, one line
+/// at a time, so long sessions are never held in memory whole. Lines that
+/// don't parse are logged and skipped.
+pub(crate) fn read_recording_with(
+    path: &str,
+    mut visit: impl FnMut(InputEventPayload),
+) -> Result<(), String> {
+    let file = File::open(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("Cannot read {}: {}", path, e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<InputEventPayload>(&line) {
+            Ok(payload) => visit(payload),
+            Err(e) => log::warn!("Skipping line {} of {}: {}", index + 1, path, e),
+        }
+    }
+    Ok(())
+}
+
+/// Reads a whole recording written by `start`.
+fn read_recording(path: &str) -> Result<Vec<InputEventPayload>, String> {
+    let mut events = Vec::new();
+    read_recording_with(path, |payload| events.push(payload))?;
+    Ok(events)
+}
+
+/// Re-emits a recording on `input-event`, keeping the original gaps between
+/// events divided by `speed`. Timestamps are shifted to the replay time so
+/// the overlay's TTLs still apply. Live capture is paused for the duration
+/// and `Replay Started`/`Replay Finished` system events bracket it.
+pub(crate) fn replay(
+    app: AppHandle,
+    state: Arc<CaptureState>,
+    path: &str,
+    speed: f64,
+) -> Result<(), String> {
+    if !speed.is_finite() || speed <= 0.0 {
+        return Err(format!("Invalid replay speed: {}", speed));
+    }
+    let events = read_recording(path)?;
+    if state.replaying.swap(true, Ordering::SeqCst) {
+        return Err("A replay is already running".to_string());
+    }
+    log::info!(
+        "Replaying {} events from {} at {}x",
+        events.len(),
+        path,
+        speed
+    );
+    thread::spawn(move || {
+        let paused_before = state.is_paused();
+        state.set_paused(true);
+        emit_system(&app, &state, "Replay Started");
+
+        let start = get_timestamp();
+        let first = events.first().map_or(0, |e| e.timestamp);
+        let mut previous = first;
+        for mut payload in events {
+            let gap_ms = payload.timestamp.saturating_sub(previous);
+            previous = payload.timestamp.max(previous);
+            if gap_ms > 0 {
+                thread::sleep(Duration::from_secs_f64(gap_ms as f64 / 1000.0 / speed));
+            }
+            let offset_ms = payload.timestamp.saturating_sub(first) as f64 / speed;
+            payload.timestamp = start + offset_ms as u128;
+            let _ = app.emit("input-event", payload);
+        }
+
+        if !paused_before {
+            state.set_paused(false);
+        }
+        emit_system(&app, &state, "Replay Finished");
+        state.replaying.store(false, Ordering::SeqCst);
+    });
+    Ok(())
+}
+
+/// Stops the running recording. Returns whether one was running.
+pub(crate) fn stop(app: &AppHandle, state: &CaptureState) -> bool {
+    // Dropping the sender lets the writer drain what's queued and exit
+    let was_recording = state.recorder.lock().unwrap().take().is_some();
+    if was_recording {
+        state.set_recording(false);
+        log::info!("Recording stopped");
+        emit_recording_state(app, state);
+    }
+    was_recording
+}
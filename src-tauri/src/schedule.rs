@@ -0,0 +1,48 @@
+use chrono::Local;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::input_capture::emit_system;
+use crate::state::CaptureState;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Pauses capture while the local time is inside a configured quiet window and
+/// resumes it afterwards. Only transitions are acted on, so the user can still
+/// toggle pause manually in between.
+pub fn start_schedule_watcher(app: AppHandle, state: Arc<CaptureState>) {
+    thread::spawn(move || {
+        let mut in_quiet_hours = false;
+        // Whether the current pause was caused by the schedule (and so may be lifted by it)
+        let mut paused_by_schedule = false;
+
+        loop {
+            // `Local` follows the system timezone, including DST changes
+            let now = Local::now().time();
+            let quiet = state
+                .settings()
+                .capture_schedule
+                .iter()
+                .any(|window| window.contains(now));
+
+            if quiet && !in_quiet_hours {
+                paused_by_schedule = state.set_paused(true);
+                if paused_by_schedule {
+                    log::info!("Capture paused by schedule");
+                    emit_system(&app, &state, "Capture Paused (Schedule)");
+                }
+            } else if !quiet && in_quiet_hours && paused_by_schedule {
+                paused_by_schedule = false;
+                if state.set_paused(false) {
+                    log::info!("Capture resumed by schedule");
+                    emit_system(&app, &state, "Capture Resumed (Schedule)");
+                }
+            }
+            in_quiet_hours = quiet;
+
+            thread::sleep(CHECK_INTERVAL);
+        }
+    });
+}
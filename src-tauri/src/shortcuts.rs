@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+use crate::input_capture::hotkey_label;
+use crate::state::Settings;
+
+/// Primary shortcut modifier as it appears in `@Key[...]` labels.
+#[cfg(target_os = "macos")]
+const PRIMARY_MODIFIER: &str = "Meta";
+#[cfg(not(target_os = "macos"))]
+const PRIMARY_MODIFIER: &str = "Ctrl";
+
+/// Well-known shortcuts as (key, semantic name), combined with the platform's
+/// primary modifier. Used for previews and for annotating exports.
+const SEMANTIC_SHORTCUTS: &[(&str, &str)] = &[
+    ("C", "Copy"),
+    ("X", "Cut"),
+    ("V", "Paste"),
+    ("Z", "Undo"),
+    ("Y", "Redo"),
+    ("A", "Select All"),
+    ("S", "Save"),
+    ("F", "Find"),
+];
+
+/// Returns the known shortcuts as (combo, semantic name), e.g. ("Ctrl+C", "Copy").
+pub fn semantic_shortcuts() -> Vec<(String, &'static str)> {
+    SEMANTIC_SHORTCUTS
+        .iter()
+        .map(|(key, name)| (format!("{}+{}", PRIMARY_MODIFIER, key), *name))
+        .collect()
+}
+
+/// Configured shortcut names keyed by the combo a press shows under
+/// `settings` (`Ctrl+C`, or `⌃+C` with Mac symbols), for annotating exports.
+pub(crate) fn shortcut_names(settings: &Settings) -> HashMap<String, String> {
+    settings
+        .shortcut_labels
+        .iter()
+        .map(|(hotkey, name)| (hotkey_label(hotkey, settings), name.clone()))
+        .collect()
+}
+
+/// Labels `preview_shortcuts` plays, one per configured shortcut in order,
+/// shown as a press would be with the name appended: `@Key[Ctrl+C (Copy)]`.
+pub(crate) fn preview_labels(settings: &Settings) -> Vec<String> {
+    settings
+        .shortcut_labels
+        .iter()
+        .map(|(hotkey, name)| format!("@Key[{} ({})]", hotkey_label(hotkey, settings), name))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{Hotkey, Modifier, ModifierStyle};
+
+    #[test]
+    fn preview_shows_configured_names() {
+        let settings = Settings {
+            modifier_order: vec![
+                Modifier::Ctrl,
+                Modifier::Alt,
+                Modifier::Shift,
+                Modifier::Meta,
+            ],
+            shortcut_labels: vec![
+                (
+                    Hotkey::parse("Ctrl+Shift+T").unwrap(),
+                    "Reopen Tab".to_string(),
+                ),
+                (Hotkey::parse("Ctrl+C").unwrap(), "Copy".to_string()),
+            ],
+            ..Settings::default()
+        };
+        assert_eq!(
+            preview_labels(&settings),
+            ["@Key[Ctrl+Shift+T (Reopen Tab)]", "@Key[Ctrl+C (Copy)]"]
+        );
+
+        let symbols = Settings {
+            modifier_style: ModifierStyle::MacSymbols,
+            ..settings
+        };
+        assert_eq!(preview_labels(&symbols)[1], "@Key[⌃+C (Copy)]");
+    }
+}
@@ -0,0 +1,908 @@
+use chrono::NaiveTime;
+use rdev::{Button, Key};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::event_server::EventServer;
+use crate::input_capture::{parse_key_name, InputEventPayload, ListenerExit, DEFAULT_LABEL_FORMAT};
+use crate::shortcuts::semantic_shortcuts;
+
+/// Axis-aligned rectangle. Coordinates are in whatever space the owning setting documents.
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+/// Daily local-time window. `end` before `start` wraps past midnight.
+#[derive(Clone, Copy, Debug, serde::Serialize)]
+pub struct ScheduleWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl ScheduleWindow {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            time >= self.start && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// Screen corner used as a hot corner.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "top-left" => Some(Self::TopLeft),
+            "top-right" => Some(Self::TopRight),
+            "bottom-left" => Some(Self::BottomLeft),
+            "bottom-right" => Some(Self::BottomRight),
+            _ => None,
+        }
+    }
+}
+
+/// How cursor positions are written in `@MouseMove[...]` labels.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CoordinateDisplay {
+    /// Global rdev screen coordinates, e.g. `@MouseMove[640, 480]`. With
+    /// several monitors these can be negative.
+    Pixels,
+    /// Pixels from the top-left of the cursor's monitor, e.g.
+    /// `@MouseMove[mon:2 40, 300]`.
+    PerMonitor,
+    /// Position within the cursor's monitor, e.g. `@MouseMove[45%, 60%]`.
+    Percent,
+}
+
+impl CoordinateDisplay {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pixels" | "global" => Some(Self::Pixels),
+            "per-monitor" => Some(Self::PerMonitor),
+            "percent" => Some(Self::Percent),
+            _ => None,
+        }
+    }
+}
+
+/// What the OS's auto-repeat of a held key produces.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyRepeatMode {
+    /// Every repeat is its own `@Key[A]`.
+    Show,
+    /// Only the initial press is shown.
+    Suppress,
+    /// One entry counting the repeats, updated in place: `@Key[A ×12]`.
+    Count,
+}
+
+impl KeyRepeatMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "show" => Some(Self::Show),
+            "suppress" => Some(Self::Suppress),
+            "count" => Some(Self::Count),
+            _ => None,
+        }
+    }
+}
+
+/// Clock used for payload `timestamp`s.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+pub enum TimestampMode {
+    /// Milliseconds since the Unix epoch. Jumps if the system clock changes.
+    #[serde(rename = "epoch_ms")]
+    Epoch,
+    /// Milliseconds on a monotonic clock started at launch.
+    #[serde(rename = "monotonic_ms")]
+    Monotonic,
+    /// Milliseconds since the current capture listener started; monotonic too.
+    #[serde(rename = "relative_ms")]
+    Relative,
+}
+
+impl TimestampMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "epoch_ms" => Some(Self::Epoch),
+            "monotonic_ms" => Some(Self::Monotonic),
+            "relative_ms" => Some(Self::Relative),
+            _ => None,
+        }
+    }
+}
+
+/// How the overlay window is positioned as the cursor moves.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlayAnchor {
+    /// Stays where it was placed.
+    Fixed,
+    /// Follows the cursor, offset so it doesn't cover it.
+    FollowCursor,
+    /// Moves to the cursor's monitor, keeping its position relative to the monitor.
+    FollowMonitor,
+}
+
+impl OverlayAnchor {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "fixed" => Some(Self::Fixed),
+            "follow_cursor" => Some(Self::FollowCursor),
+            "follow_monitor" => Some(Self::FollowMonitor),
+            _ => None,
+        }
+    }
+}
+
+/// Which label wins when the OS-provided key name and the built-in mapping
+/// table disagree on the character a key produced.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NameConflictPolicy {
+    /// Trust the OS name (it follows the active layout).
+    Os,
+    /// Trust the built-in table.
+    Manual,
+    /// Show both as `os/manual`, to spot wrong mappings.
+    Both,
+}
+
+impl NameConflictPolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "os" => Some(Self::Os),
+            "manual" => Some(Self::Manual),
+            "both" => Some(Self::Both),
+            _ => None,
+        }
+    }
+}
+
+/// A key whose OS name disagreed with the built-in mapping table.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct NameConflict {
+    pub key: String,
+    pub shift: bool,
+    pub os_name: String,
+    pub manual_name: String,
+    /// Presses that produced this disagreement.
+    pub count: u64,
+}
+
+/// Modifier as shown in key labels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+pub enum Modifier {
+    Ctrl,
+    Alt,
+    Shift,
+    Meta,
+}
+
+impl Modifier {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => Some(Self::Ctrl),
+            "alt" | "option" => Some(Self::Alt),
+            "shift" => Some(Self::Shift),
+            "meta" | "cmd" | "command" | "win" | "super" => Some(Self::Meta),
+            _ => None,
+        }
+    }
+
+    /// The modifier a physical key belongs to, if it is one.
+    pub fn from_key(key: Key) -> Option<Self> {
+        match key {
+            Key::ControlLeft | Key::ControlRight => Some(Self::Ctrl),
+            Key::Alt => Some(Self::Alt),
+            Key::ShiftLeft | Key::ShiftRight => Some(Self::Shift),
+            Key::MetaLeft | Key::MetaRight => Some(Self::Meta),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Ctrl => "Ctrl",
+            Self::Alt => "Alt",
+            Self::Shift => "Shift",
+            Self::Meta => "Meta",
+        }
+    }
+
+    /// The left and right physical keys. rdev reports right Alt as `AltGr`,
+    /// which isn't tracked as a modifier, so Alt only ever shows as left.
+    pub fn sides(self) -> (Key, Key) {
+        match self {
+            Self::Ctrl => (Key::ControlLeft, Key::ControlRight),
+            Self::Alt => (Key::Alt, Key::AltGr),
+            Self::Shift => (Key::ShiftLeft, Key::ShiftRight),
+            Self::Meta => (Key::MetaLeft, Key::MetaRight),
+        }
+    }
+
+    /// Name shown in key labels for `style`.
+    pub fn styled_label(self, style: ModifierStyle) -> &'static str {
+        match (style, self) {
+            (ModifierStyle::MacSymbols, Self::Ctrl) => "⌃",
+            (ModifierStyle::MacSymbols, Self::Alt) => "⌥",
+            (ModifierStyle::MacSymbols, Self::Shift) => "⇧",
+            (ModifierStyle::MacSymbols, Self::Meta) => "⌘",
+            (ModifierStyle::Win, Self::Meta) => "Win",
+            _ => self.label(),
+        }
+    }
+
+    /// Conventional modifier order for the current platform: ⌃⌥⇧⌘ on macOS,
+    /// Win+Ctrl+Alt+Shift on Windows.
+    pub fn platform_order() -> Vec<Self> {
+        if cfg!(target_os = "windows") {
+            vec![Self::Meta, Self::Ctrl, Self::Alt, Self::Shift]
+        } else {
+            vec![Self::Ctrl, Self::Alt, Self::Shift, Self::Meta]
+        }
+    }
+}
+
+/// How modifiers are written in key labels.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ModifierStyle {
+    /// `Ctrl`, `Alt`, `Shift`, `Meta`
+    Text,
+    /// `⌃`, `⌥`, `⇧`, `⌘`, with Meta as the Command key
+    MacSymbols,
+    /// `Ctrl`, `Alt`, `Shift`, `Win`
+    Win,
+}
+
+impl ModifierStyle {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "text" => Some(Self::Text),
+            "mac-symbols" => Some(Self::MacSymbols),
+            "win" => Some(Self::Win),
+            _ => None,
+        }
+    }
+}
+
+/// Built-in keyboard layouts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum KeyboardLayout {
+    #[serde(rename = "us")]
+    UsQwerty,
+    #[serde(rename = "jis")]
+    Jis,
+    #[serde(rename = "azerty")]
+    Azerty,
+}
+
+impl KeyboardLayout {
+    pub const ALL: &'static [Self] = &[Self::UsQwerty, Self::Jis, Self::Azerty];
+
+    /// Stable identifier used by commands and in payloads.
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::UsQwerty => "us",
+            Self::Jis => "jis",
+            Self::Azerty => "azerty",
+        }
+    }
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Self::UsQwerty => "US (QWERTY)",
+            Self::Jis => "Japanese (JIS)",
+            Self::Azerty => "French (AZERTY)",
+        }
+    }
+
+    /// Whether the layout has dead keys, which type nothing until the next key.
+    pub fn has_dead_keys(self) -> bool {
+        matches!(self, Self::Azerty)
+    }
+
+    /// Parses a layout id, case-insensitive (`"us"`, `"JIS"`, ...).
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|layout| layout.id().eq_ignore_ascii_case(value.trim()))
+    }
+}
+
+/// A key combined with a set of modifiers, written like `Ctrl+Alt+Shift+K`.
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct Hotkey {
+    pub modifiers: Vec<Modifier>,
+    pub key: Key,
+}
+
+impl Hotkey {
+    /// Parses `Mod+Mod+Key`. Modifier and key names are case-insensitive.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut parts: Vec<&str> = value.split('+').map(str::trim).collect();
+        let key_name = parts.pop().filter(|k| !k.is_empty());
+        let key = key_name
+            .and_then(parse_key_name)
+            .filter(|k| Modifier::from_key(*k).is_none())
+            .ok_or_else(|| format!("Missing or unknown key in hotkey: {}", value))?;
+        let mut modifiers = Vec::new();
+        for part in parts {
+            let modifier =
+                Modifier::parse(part).ok_or_else(|| format!("Unknown modifier: {}", part))?;
+            if !modifiers.contains(&modifier) {
+                modifiers.push(modifier);
+            }
+        }
+        Ok(Self { modifiers, key })
+    }
+
+    /// Same key and modifiers, in any order.
+    pub fn same_combo(&self, other: &Hotkey) -> bool {
+        self.key == other.key
+            && self.modifiers.len() == other.modifiers.len()
+            && self.modifiers.iter().all(|m| other.modifiers.contains(m))
+    }
+
+    /// True if `key` was pressed with exactly this hotkey's modifiers held.
+    pub fn matches(&self, pressed_modifiers: &HashSet<Key>, key: Key) -> bool {
+        if key != self.key {
+            return false;
+        }
+        // Left and right variants of a modifier count once
+        let mut held: Vec<Modifier> = Vec::new();
+        for modifier in pressed_modifiers
+            .iter()
+            .filter_map(|k| Modifier::from_key(*k))
+        {
+            if !held.contains(&modifier) {
+                held.push(modifier);
+            }
+        }
+        held.len() == self.modifiers.len() && self.modifiers.iter().all(|m| held.contains(m))
+    }
+}
+
+/// Formats as `Ctrl+Alt+KeyP`, which `Hotkey::parse` reads back.
+impl std::fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for modifier in &self.modifiers {
+            write!(f, "{}+", modifier.label())?;
+        }
+        write!(f, "{:?}", self.key)
+    }
+}
+
+/// User-tunable capture settings. The capture thread reads a snapshot per event,
+/// so changes take effect immediately without restarting capture.
+#[derive(Clone, serde::Serialize)]
+pub struct Settings {
+    /// Emit an `@Scrolled[N px]` milestone every this many pixels (0 disables).
+    pub scroll_milestone_px: u64,
+    /// Make the overlay click-through except while the cursor hovers one of
+    /// `interactive_regions`.
+    pub auto_click_through: bool,
+    /// Quiet hours (local time) during which capture is paused automatically.
+    pub capture_schedule: Vec<ScheduleWindow>,
+    /// Dwelling in this screen corner toggles the overlay's visibility.
+    pub hot_corner: Option<Corner>,
+    /// Regions where the overlay accepts the cursor, in rdev screen coordinates.
+    pub interactive_regions: Vec<Rect>,
+    /// Emit `@KeyUp[Key Nms]` on key release with how long the key was held.
+    pub emit_key_release: bool,
+    /// Non-modifier keys held at least this long emit `@KeyHold[A 0.8s]` on
+    /// release (0 disables).
+    pub key_hold_threshold_ms: u64,
+    pub key_repeat_mode: KeyRepeatMode,
+    /// Show a modifier pressed and released on its own (e.g. tap-Shift) as
+    /// `@Key[Shift]`. Modifiers used in a combo only appear in the combo.
+    pub show_lone_modifiers: bool,
+    /// Label modifiers by side (`LCtrl`, `RShift`) instead of merging left and
+    /// right. Both sides held at once still show the plain name.
+    pub distinguish_modifier_side: bool,
+    /// Replace typed characters with `privacy_mask` (e.g. while entering a
+    /// password). Shortcuts and named keys like Enter stay visible, and the
+    /// key heatmap stops counting.
+    pub privacy_mode: bool,
+    pub privacy_mask: String,
+    /// How labels are written for the overlay, with `{type}` and `{value}`
+    /// placeholders. The default keeps the `@Key[Ctrl+S]` form.
+    pub label_format: String,
+    /// Emit a `stats` event with keys and clicks per minute every second.
+    pub stats_enabled: bool,
+    /// Keys that never produce events (e.g. a password-manager hotkey).
+    pub muted_keys: HashSet<Key>,
+    /// Trust rdev's OS-provided `event.name` for printable keys. It follows the
+    /// active layout and Shift state, but some platforms report wrong or stale
+    /// names; turning it off forces the built-in mapping tables instead.
+    pub prefer_os_name: bool,
+    /// Order modifiers are joined in within key labels.
+    pub modifier_order: Vec<Modifier>,
+    /// How modifier names are written in key labels.
+    pub modifier_style: ModifierStyle,
+    /// Attach `delta_ms` (time since the previous emitted event) to payloads.
+    pub include_delta: bool,
+    /// Attach the rdev key or button name (`raw`) to key and mouse button
+    /// payloads. Not added for keys while privacy mode is on.
+    pub include_raw: bool,
+    /// Events are dropped for this long after capture starts, so keys held or a
+    /// drag in progress at launch don't produce a burst. Read when capture starts.
+    pub startup_settling_ms: u64,
+    /// Keystrokes closer together than this are treated as a paste or macro and
+    /// collapsed into one `@Paste[N chars]` event (0 disables).
+    pub paste_threshold_ms: u64,
+    /// Show consecutive printable keystrokes as one `@Type[hello]` event
+    /// instead of a key event each. Off by default.
+    pub text_aggregation: bool,
+    /// Count key presses per key for `get_key_heatmap`. Off by default.
+    pub key_heatmap: bool,
+    /// Wheel events closer together than this (in the same direction) extend
+    /// one `@Scroll` gesture instead of starting a new one.
+    pub scroll_gesture_gap_ms: u64,
+    /// Pixels per notch for `@Scroll[Down 3]` labels; `None` shows the
+    /// distance in pixels instead (`@Scroll[Down 300px]`).
+    pub scroll_step_px: Option<f64>,
+    /// Show `system` events (pause/resume, arming, ...) in the overlay.
+    pub show_system_events: bool,
+    /// Emit `@Dwell[x, y]` after the cursor rests this long without clicking (0 disables).
+    pub dwell_ms: u64,
+    /// Send an `idle` event after this long without input events, so the
+    /// overlay can hide itself (0 disables).
+    pub idle_timeout_ms: u64,
+    /// How far the cursor may drift, in rdev screen units, and still count as resting.
+    pub dwell_radius_px: f64,
+    /// Scale factor used instead of the detected one for coordinate conversion.
+    pub coordinate_scale_override: Option<f64>,
+    /// Event types emitted at all, e.g. `["key", "shortcut"]`. Empty allows
+    /// every type; `system` events and overlay clears always pass.
+    pub event_filter: Vec<String>,
+    /// Only show key presses that form a Ctrl/Alt/Meta shortcut; plain typing
+    /// (including Shift+letter) is hidden, along with its releases and holds,
+    /// lone modifier taps and aggregated text.
+    pub shortcut_only: bool,
+    /// Drop plain input and only show events around "interesting" ones.
+    pub interesting_only: bool,
+    /// How long everything is shown after an interesting event.
+    pub interesting_window_ms: u64,
+    /// What counts as interesting: `"shortcut"` (a key with Ctrl/Alt/Meta) or
+    /// an event type such as `"doubleclick"` or `"drag"`.
+    pub interesting_triggers: Vec<String>,
+    /// Keyboard layout the user selected, tagged onto captured events. Its table
+    /// resolves keys the OS doesn't name (JIS when unset). Persisted.
+    pub keyboard_layout: Option<KeyboardLayout>,
+    /// Movement (in rdev screen units) a held mouse button needs before it counts as a drag.
+    pub drag_threshold_px: f64,
+    /// Drag threshold for touch-generated pointer events; `None` uses the mouse
+    /// threshold. Only applies where the event source can be told apart.
+    pub touch_drag_threshold_px: Option<f64>,
+    /// Toggles the user pause. Needs at least one modifier.
+    pub pause_hotkey: Hotkey,
+    /// Disarms capture and hides the overlay immediately. Needs two modifiers.
+    /// Off by default, so no global binding is taken without asking.
+    pub panic_hotkey: Option<Hotkey>,
+    /// Wipes the overlay, like `clear_overlay`. Off by default.
+    pub clear_hotkey: Option<Hotkey>,
+    /// Shortcuts with their semantic name (`Ctrl+C` is "Copy"), in the order
+    /// `preview_shortcuts` plays them. Defaults to the well-known shortcuts.
+    pub shortcut_labels: Vec<(Hotkey, String)>,
+    /// CSS color of the overlay's "recording" dot. Persisted.
+    pub recording_indicator_color: String,
+    /// Mouse events are only shown while the cursor is inside this rect (rdev
+    /// screen coordinates). `None` means the whole screen.
+    pub mouse_roi: Option<Rect>,
+    /// Also drop clicks, drags and scrolls outside `mouse_roi`, not just moves.
+    pub roi_filters_clicks: bool,
+    /// How long the overlay keeps each event type on screen, in ms. Sent on
+    /// every payload as `ttl_ms`; types without an entry never expire.
+    pub ttl_by_type: HashMap<String, u64>,
+    /// Held modifiers are assumed stuck (their release was missed) when a key
+    /// event arrives after this long without one. 0 disables the reset.
+    pub stuck_modifier_timeout_ms: u64,
+    /// Omit the second `@Click` of a double-click, so only `@DoubleClick` is shown.
+    pub suppress_click_on_doubleclick: bool,
+    /// Whether `@MouseMove` labels show pixels or a percentage of the monitor.
+    pub coordinate_display: CoordinateDisplay,
+    /// Clock payload timestamps are reported in.
+    pub timestamp_mode: TimestampMode,
+    /// Decimal places in `@MouseMove` coordinates.
+    pub mouse_precision: u8,
+    /// Snap `@MouseMove` pixel positions to this grid (0 disables), which
+    /// steadies the label while the cursor jitters.
+    pub mouse_grid_px: u32,
+    /// Drop `@MouseMove` for this many ms after a key press (0 disables).
+    /// Clicks and drags are unaffected.
+    pub suppress_move_while_typing_ms: u64,
+    /// Prefix scroll labels with held modifiers, e.g. `@Scroll[Ctrl+Down 300px]`.
+    pub scroll_modifiers: bool,
+    /// Whether the overlay stays put or follows the cursor. Persisted.
+    pub overlay_anchor: OverlayAnchor,
+    /// Resolution when `prefer_os_name` is on and the OS name disagrees with the
+    /// built-in table. Disagreements are recorded for `get_name_conflicts` either way.
+    pub name_conflict_policy: NameConflictPolicy,
+    /// Skip all interpretation (clicks, drags, labels, layouts, shortcuts) and
+    /// emit one minimal `keydown`/`keyup`/`mousedown`/`mouseup`/`mousemove`/
+    /// `wheel` event per input, for frontends that do their own processing.
+    /// The pause and panic hotkeys and muted keys still apply.
+    pub raw_passthrough: bool,
+    /// Two clicks of the same button closer together than this are a double-click.
+    pub double_click_threshold_ms: u64,
+    /// At most one `@MouseMove` per this many ms; the latest position is
+    /// always emitted once movement pauses (0 emits every move).
+    pub mousemove_throttle_ms: u64,
+}
+
+/// Default on-screen lifetimes: pointer noise vanishes quickly, while keys,
+/// clicks and status messages linger long enough to read. The overlay acts on
+/// `stats`, `idle` and `clear` rather than listing them; their TTLs are for
+/// event-server clients.
+fn default_ttl_by_type() -> HashMap<String, u64> {
+    [
+        ("mousemove", 800),
+        ("wheel", 800),
+        ("pen", 800),
+        ("mousedown", 1500),
+        ("mouseup", 1500),
+        ("dragstart", 1500),
+        ("keydown", 1500),
+        ("keyup", 1500),
+        ("keyhold", 2500),
+        ("caption", 1500),
+        ("scroll", 2000),
+        ("click", 2500),
+        ("mousechord", 2500),
+        ("drag", 2500),
+        ("dwell", 2500),
+        ("doubleclick", 3000),
+        ("tripleclick", 3000),
+        ("key", 3000),
+        ("shortcut", 3000),
+        ("paste", 3000),
+        ("scrolled", 3000),
+        ("stats", 3000),
+        ("text", 4000),
+        ("system", 4000),
+        ("idle", 4000),
+        ("clear", 4000),
+    ]
+    .into_iter()
+    .map(|(event_type, ms)| (event_type.to_string(), ms))
+    .collect()
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            scroll_milestone_px: 0,
+            auto_click_through: false,
+            capture_schedule: Vec::new(),
+            hot_corner: None,
+            interactive_regions: Vec::new(),
+            emit_key_release: false,
+            key_hold_threshold_ms: 500,
+            key_repeat_mode: KeyRepeatMode::Show,
+            show_lone_modifiers: false,
+            distinguish_modifier_side: false,
+            privacy_mode: false,
+            privacy_mask: "•".to_string(),
+            label_format: DEFAULT_LABEL_FORMAT.to_string(),
+            stats_enabled: false,
+            muted_keys: HashSet::new(),
+            prefer_os_name: true,
+            modifier_order: Modifier::platform_order(),
+            modifier_style: ModifierStyle::Text,
+            include_delta: false,
+            include_raw: false,
+            startup_settling_ms: 200,
+            paste_threshold_ms: 8,
+            text_aggregation: false,
+            key_heatmap: false,
+            scroll_gesture_gap_ms: 100,
+            scroll_step_px: None,
+            show_system_events: true,
+            dwell_ms: 0,
+            idle_timeout_ms: 0,
+            dwell_radius_px: 10.0,
+            coordinate_scale_override: None,
+            event_filter: Vec::new(),
+            shortcut_only: false,
+            interesting_only: false,
+            interesting_window_ms: 2000,
+            interesting_triggers: vec!["shortcut".to_string(), "doubleclick".to_string()],
+            keyboard_layout: None,
+            drag_threshold_px: 10.0,
+            touch_drag_threshold_px: None,
+            pause_hotkey: Hotkey {
+                modifiers: vec![Modifier::Ctrl, Modifier::Alt],
+                key: Key::KeyP,
+            },
+            panic_hotkey: None,
+            clear_hotkey: None,
+            shortcut_labels: semantic_shortcuts()
+                .into_iter()
+                .filter_map(|(combo, name)| Some((Hotkey::parse(&combo).ok()?, name.to_string())))
+                .collect(),
+            recording_indicator_color: "#ef4444".to_string(),
+            mouse_roi: None,
+            roi_filters_clicks: true,
+            ttl_by_type: default_ttl_by_type(),
+            stuck_modifier_timeout_ms: 30_000,
+            suppress_click_on_doubleclick: false,
+            coordinate_display: CoordinateDisplay::Pixels,
+            timestamp_mode: TimestampMode::Epoch,
+            mouse_precision: 0,
+            mouse_grid_px: 0,
+            suppress_move_while_typing_ms: 0,
+            scroll_modifiers: true,
+            overlay_anchor: OverlayAnchor::Fixed,
+            name_conflict_policy: NameConflictPolicy::Os,
+            raw_passthrough: false,
+            double_click_threshold_ms: 300,
+            mousemove_throttle_ms: 50,
+        }
+    }
+}
+
+/// Runtime capture state shared between the capture thread and the Tauri commands.
+pub struct CaptureState {
+    settings: Mutex<Arc<Settings>>,
+    /// Programmatic arming used by recording pipelines. Unlike pause (which the
+    /// user toggles with the hotkey), nothing is emitted while disarmed.
+    armed: AtomicBool,
+    /// Whether a session recording is being written.
+    recording: AtomicBool,
+    /// User pause, toggled by the hotkey or the capture schedule.
+    paused: AtomicBool,
+    /// Most recent cursor position reported by rdev.
+    pub last_mouse_pos: Mutex<Option<(f64, f64)>>,
+    /// Modifier keys currently held down.
+    pub pressed_modifiers: Mutex<HashSet<Key>>,
+    /// Mouse buttons currently held down, in press order.
+    pub pressed_buttons: Mutex<Vec<Button>>,
+    /// Total `input-event` payloads emitted since launch.
+    pub events_emitted: AtomicU64,
+    /// Last error reported by the rdev listener, if it stopped.
+    pub last_error: Mutex<Option<String>>,
+    /// Whether the rdev listener is running (its thread ends if `listen` fails).
+    pub listener_running: AtomicBool,
+    /// Whether the capture thread (which restarts the listener) is alive.
+    pub capture_thread_running: AtomicBool,
+    /// Set by `stop_capture`: the listener is ended where the platform allows
+    /// it, and otherwise drops everything until capture is started again.
+    pub capture_stopped: AtomicBool,
+    /// Reaches the thread blocked in rdev's `listen`, while one is.
+    pub listener_exit: Mutex<Option<ListenerExit>>,
+    /// Timestamp of the last event rdev delivered (0 before the first one).
+    pub last_input_ms: AtomicU64,
+    /// When the last input event was emitted to the overlay (0 before the
+    /// first one). System events and the activity meter don't count.
+    pub last_activity_ms: AtomicU64,
+    /// Origin of `monotonic_ms` timestamps.
+    pub launched_at: Instant,
+    /// Origin of `relative_ms` timestamps, reset when a listener starts.
+    pub capture_started_at: Mutex<Instant>,
+    /// Payloads dropped because the pipeline queue was full.
+    pub events_dropped: AtomicU64,
+    /// Total scroll distance in pixels since launch.
+    pub scroll_distance_px: AtomicU64,
+    /// Timestamp of the last emitted `input-event` (0 before the first one).
+    pub last_emit_ms: AtomicU64,
+    /// Bumped to start or cancel a shortcut preview; a running preview stops
+    /// as soon as the generation it was started with is no longer current.
+    pub preview_generation: AtomicU64,
+    /// Capture-to-emit latency of recent events in microseconds, oldest first.
+    pub emit_latency_us: Mutex<VecDeque<u64>>,
+    /// Press count per key name, collected while `key_heatmap` is enabled.
+    pub key_heatmap: Mutex<HashMap<String, u64>>,
+    /// Total mouse button presses since launch.
+    pub button_presses: AtomicU64,
+    /// Total non-modifier key presses since launch, excluding auto-repeat.
+    pub key_presses: AtomicU64,
+    /// Most recently emitted events, oldest first. Updates of one gesture
+    /// replace each other, as they do in the overlay.
+    pub(crate) recent_events: Mutex<VecDeque<InputEventPayload>>,
+    /// Feeds the recording writer thread while a recording is running.
+    pub(crate) recorder: Mutex<Option<Sender<InputEventPayload>>>,
+    /// WebSocket server mirroring overlay events, while one is running.
+    pub(crate) event_server: Mutex<Option<EventServer>>,
+    /// Bumped to cancel a pending recording countdown.
+    pub countdown_generation: AtomicU64,
+    /// Whether a recording is being replayed to the overlay.
+    pub replaying: AtomicBool,
+    /// Frame of the focused (frontmost) window, refreshed periodically. `None`
+    /// when the platform can't report it.
+    pub focused_window: Mutex<Option<Rect>>,
+    /// OS-name vs. mapping-table disagreements per key and Shift state.
+    pub name_conflicts: Mutex<HashMap<(Key, bool), NameConflict>>,
+    /// When `emit_test_event` last emitted, for its rate limit.
+    pub last_test_event: Mutex<Option<Instant>>,
+}
+
+/// Number of emitted events kept in `recent_events`.
+const RECENT_EVENTS: usize = 200;
+
+/// Number of latency samples kept for `measure_emit_latency`.
+const LATENCY_SAMPLES: usize = 512;
+
+impl Default for CaptureState {
+    fn default() -> Self {
+        Self {
+            settings: Mutex::new(Arc::new(Settings::default())),
+            // Armed by default so capture behaves as before unless a pipeline opts in
+            armed: AtomicBool::new(true),
+            recording: AtomicBool::new(false),
+            paused: AtomicBool::new(false),
+            last_mouse_pos: Mutex::new(None),
+            pressed_modifiers: Mutex::new(HashSet::new()),
+            pressed_buttons: Mutex::new(Vec::new()),
+            events_emitted: AtomicU64::new(0),
+            last_error: Mutex::new(None),
+            listener_running: AtomicBool::new(false),
+            capture_thread_running: AtomicBool::new(false),
+            capture_stopped: AtomicBool::new(false),
+            listener_exit: Mutex::new(None),
+            last_input_ms: AtomicU64::new(0),
+            last_activity_ms: AtomicU64::new(0),
+            launched_at: Instant::now(),
+            capture_started_at: Mutex::new(Instant::now()),
+            events_dropped: AtomicU64::new(0),
+            scroll_distance_px: AtomicU64::new(0),
+            last_emit_ms: AtomicU64::new(0),
+            preview_generation: AtomicU64::new(0),
+            emit_latency_us: Mutex::new(VecDeque::with_capacity(LATENCY_SAMPLES)),
+            key_heatmap: Mutex::new(HashMap::new()),
+            button_presses: AtomicU64::new(0),
+            key_presses: AtomicU64::new(0),
+            recent_events: Mutex::new(VecDeque::with_capacity(RECENT_EVENTS)),
+            recorder: Mutex::new(None),
+            event_server: Mutex::new(None),
+            countdown_generation: AtomicU64::new(0),
+            replaying: AtomicBool::new(false),
+            focused_window: Mutex::new(None),
+            name_conflicts: Mutex::new(HashMap::new()),
+            last_test_event: Mutex::new(None),
+        }
+    }
+}
+
+impl CaptureState {
+    /// Returns the current settings snapshot (cheap, only clones the `Arc`).
+    pub fn settings(&self) -> Arc<Settings> {
+        self.settings.lock().unwrap().clone()
+    }
+
+    pub fn update_settings(&self, update: impl FnOnce(&mut Settings)) {
+        let mut guard = self.settings.lock().unwrap();
+        let mut next = (**guard).clone();
+        update(&mut next);
+        *guard = Arc::new(next);
+    }
+
+    pub fn is_armed(&self) -> bool {
+        self.armed.load(Ordering::SeqCst)
+    }
+
+    /// Sets the armed flag and returns whether it actually changed.
+    pub fn set_armed(&self, armed: bool) -> bool {
+        self.armed.swap(armed, Ordering::SeqCst) != armed
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.load(Ordering::SeqCst)
+    }
+
+    pub fn set_recording(&self, recording: bool) {
+        self.recording.store(recording, Ordering::SeqCst);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Sets the paused flag and returns whether it actually changed.
+    pub fn set_paused(&self, paused: bool) -> bool {
+        self.paused.swap(paused, Ordering::SeqCst) != paused
+    }
+
+    /// Flips the paused flag and returns the new value.
+    pub fn toggle_paused(&self) -> bool {
+        !self.paused.fetch_xor(true, Ordering::SeqCst)
+    }
+
+    pub fn record_emit_latency(&self, latency: Duration) {
+        let mut samples = self.emit_latency_us.lock().unwrap();
+        if samples.len() == LATENCY_SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back(latency.as_micros() as u64);
+    }
+
+    /// Counts a press where the OS name and the mapping table disagreed. The
+    /// first occurrence per key and Shift state is logged. Nothing is kept in
+    /// privacy mode.
+    pub fn record_name_conflict(&self, key: Key, shift: bool, os_name: &str, manual_name: &str) {
+        // Both names are the typed character; keep them out of memory and the log
+        if self.settings().privacy_mode {
+            return;
+        }
+        let mut conflicts = self.name_conflicts.lock().unwrap();
+        let conflict = conflicts.entry((key, shift)).or_insert_with(|| {
+            log::debug!(
+                "Key name mismatch for {:?} (shift: {}): OS \"{}\", table \"{}\"",
+                key,
+                shift,
+                os_name,
+                manual_name
+            );
+            NameConflict {
+                key: format!("{:?}", key),
+                shift,
+                os_name: os_name.to_string(),
+                manual_name: manual_name.to_string(),
+                count: 0,
+            }
+        });
+        // The OS name can change with the active layout
+        conflict.os_name = os_name.to_string();
+        conflict.count += 1;
+    }
+
+    pub(crate) fn record_recent_event(&self, payload: &InputEventPayload) {
+        let mut events = self.recent_events.lock().unwrap();
+        if let (Some(last), Some(id)) = (events.back_mut(), payload.gesture_id) {
+            if last.gesture_id == Some(id) {
+                *last = payload.clone();
+                return;
+            }
+        }
+        if events.len() == RECENT_EVENTS {
+            events.pop_front();
+        }
+        events.push_back(payload.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input_capture::EVENT_TYPES;
+
+    #[test]
+    fn every_emitted_type_has_a_default_ttl() {
+        let ttl_by_type = default_ttl_by_type();
+        for event_type in EVENT_TYPES {
+            assert!(ttl_by_type.contains_key(*event_type), "{}", event_type);
+        }
+        assert_eq!(
+            ttl_by_type.len(),
+            EVENT_TYPES.len(),
+            "no TTLs for unknown types"
+        );
+    }
+}
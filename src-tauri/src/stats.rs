@@ -0,0 +1,65 @@
+use std::collections::VecDeque;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+use crate::input_capture::{emit_event, InputEventPayload};
+use crate::state::CaptureState;
+
+const TICK: Duration = Duration::from_secs(1);
+/// Rates are averaged over this much history.
+const WINDOW: Duration = Duration::from_secs(60);
+/// Every `stats` payload shares this gesture id, so the overlay keeps a single
+/// entry that updates in place. Capture assigns gesture ids counting up from 1.
+const STATS_GESTURE_ID: u64 = u64::MAX;
+
+/// Events per minute between `then` and `now` press totals.
+fn per_minute(now: u64, then: u64, elapsed: Duration) -> u64 {
+    let secs = elapsed.as_secs_f64().max(1.0);
+    (now.saturating_sub(then) as f64 * 60.0 / secs).round() as u64
+}
+
+/// While `stats_enabled` is on, emits `@Stats[120 KPM, 30 CPM]` once per
+/// second: key and mouse-button presses per minute over the last minute,
+/// sampled from the capture thread's press counters.
+pub fn start_stats_watcher(app: AppHandle, state: Arc<CaptureState>) {
+    thread::spawn(move || {
+        // (when, key presses, button presses), oldest first
+        let mut samples: VecDeque<(Instant, u64, u64)> = VecDeque::new();
+        loop {
+            thread::sleep(TICK);
+
+            if !state.settings().stats_enabled {
+                samples.clear();
+                continue;
+            }
+            let now = Instant::now();
+            let keys = state.key_presses.load(Ordering::Relaxed);
+            let clicks = state.button_presses.load(Ordering::Relaxed);
+            samples.push_back((now, keys, clicks));
+            while samples
+                .front()
+                .is_some_and(|(at, _, _)| now.duration_since(*at) > WINDOW)
+            {
+                samples.pop_front();
+            }
+            if !state.is_armed() || state.is_paused() {
+                continue;
+            }
+
+            let (since, keys_then, clicks_then) = samples[0];
+            let elapsed = now.duration_since(since);
+            let kpm = per_minute(keys, keys_then, elapsed);
+            let cpm = per_minute(clicks, clicks_then, elapsed);
+            emit_event(
+                &app,
+                &state,
+                InputEventPayload::now("stats", format!("@Stats[{} KPM, {} CPM]", kpm, cpm))
+                    .with_stats(kpm, cpm)
+                    .with_gesture(STATS_GESTURE_ID),
+            );
+        }
+    });
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use crate::input_capture::InputEventPayload;
+
+/// Text inside the outer brackets of a label, e.g. `Ctrl+C` for `@Key[Ctrl+C]`.
+pub(crate) fn label_content(label: &str) -> &str {
+    match (label.find('['), label.rfind(']')) {
+        (Some(start), Some(end)) if start < end => &label[start + 1..end],
+        _ => label,
+    }
+}
+
+/// `mm:ss.d` relative to the start of the transcript.
+fn format_offset(ms: u128) -> String {
+    format!("{:02}:{:02}.{}", ms / 60_000, ms / 1000 % 60, ms / 100 % 10)
+}
+
+/// Human-readable line for a single event, or `None` for events that would only
+/// add noise to a transcript (cursor moves, button down/up, key releases).
+fn describe(
+    payload: &InputEventPayload,
+    shortcut_names: &HashMap<String, String>,
+) -> Option<String> {
+    let content = label_content(&payload.label);
+    let line = match payload.event_type.as_str() {
+        // Spaces only separate typed words
+        "key" if content == "Space" => return None,
+        "key" | "shortcut" => match shortcut_names.get(content) {
+            Some(name) => format!("{} ({})", content, name),
+            None => content.to_string(),
+        },
+        "click" => format!("Click {}", content),
+        "doubleclick" => format!("Double-click {}", content),
+        "tripleclick" => format!("Triple-click {}", content),
+        "mousechord" => format!("Press {} together", content),
+        "drag" => format!("Drag {}", content),
+        "scroll" => format!("Scroll {}", content),
+        "paste" => format!("Paste {}", content),
+        "text" => format!("Typed \"{}\"", content),
+        "keyhold" => format!("Hold {}", content),
+        "dwell" => format!("Dwell at ({})", content),
+        "system" => format!("[{}]", payload.label),
+        "mousemove" | "mousedown" | "mouseup" | "dragstart" | "keyup" | "stats" | "idle" => {
+            return None
+        }
+        _ => content.to_string(),
+    };
+    Some(line)
+}
+
+/// Whether a key label is a single printable character that can be joined into a word.
+fn typed_char(payload: &InputEventPayload) -> Option<&str> {
+    let content = label_content(&payload.label);
+    (payload.event_type == "key" && content.chars().count() == 1).then_some(content)
+}
+
+/// Renders events as `mm:ss.d  description` lines. Consecutive typed characters
+/// are grouped into words (`Typed "hello"`); spaces end a word. Shortcuts in
+/// `shortcut_names` (see `shortcuts::shortcut_names`) get their name appended.
+pub fn render_transcript(
+    events: &[InputEventPayload],
+    shortcut_names: &HashMap<String, String>,
+) -> String {
+    let Some(first) = events.first() else {
+        return String::new();
+    };
+    let start = first.timestamp;
+
+    let mut out = String::new();
+    let mut word: Option<(u128, String)> = None;
+    let flush_word = |out: &mut String, word: &mut Option<(u128, String)>| {
+        if let Some((at, text)) = word.take() {
+            out.push_str(&format!(
+                "{}  Typed \"{}\"\n",
+                format_offset(at.saturating_sub(start)),
+                text
+            ));
+        }
+    };
+
+    for event in events {
+        if let Some(c) = typed_char(event) {
+            word.get_or_insert_with(|| (event.timestamp, String::new()))
+                .1
+                .push_str(c);
+            continue;
+        }
+        flush_word(&mut out, &mut word);
+        if let Some(line) = describe(event, shortcut_names) {
+            out.push_str(&format!(
+                "{}  {}\n",
+                format_offset(event.timestamp.saturating_sub(start)),
+                line
+            ));
+        }
+    }
+    flush_word(&mut out, &mut word);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shortcuts::shortcut_names;
+    use crate::state::{Hotkey, ModifierStyle, Settings};
+
+    #[test]
+    fn configured_names_annotate_styled_shortcuts() {
+        let settings = Settings {
+            modifier_style: ModifierStyle::MacSymbols,
+            shortcut_labels: vec![(Hotkey::parse("Ctrl+K").unwrap(), "Link".to_string())],
+            ..Settings::default()
+        };
+        let events = [
+            InputEventPayload::new("key", "@Key[h]", 1000),
+            InputEventPayload::new("key", "@Key[i]", 1100),
+            InputEventPayload::new("shortcut", "@Key[⌃+K]", 2300),
+        ];
+        assert_eq!(
+            render_transcript(&events, &shortcut_names(&settings)),
+            "00:00.0  Typed \"hi\"\n00:01.3  ⌃+K (Link)\n"
+        );
+    }
+
+    #[test]
+    fn no_events_give_an_empty_transcript() {
+        assert_eq!(render_transcript(&[], &HashMap::new()), "");
+    }
+}
@@ -0,0 +1,51 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::input_capture::emits_pointer_events;
+use crate::state::{CaptureState, Rect};
+
+/// Enumerating windows is too slow to do per event, so the frame is cached.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Frame of the frontmost window not owned by this process (the overlay is
+/// always on top and must be skipped, whatever its title), in the screen
+/// space the platform's window list uses.
+///
+/// What this gives `window_x`/`window_y` per platform:
+/// - Windows, macOS: xcap lists windows front to back, so the first visible
+///   one is the focused window and the fields are set on every mouse payload.
+/// - X11: the list isn't guaranteed to be in stacking order, so the fields
+///   are set but may be relative to a window other than the focused one.
+/// - Wayland: there is no window list, so the fields are never set.
+fn frontmost_window_frame() -> Option<Rect> {
+    let own_pid = std::process::id();
+    let windows = xcap::Window::all().ok()?;
+    windows
+        .iter()
+        .find(|w| !w.is_minimized() && w.width() > 0 && w.pid() != own_pid)
+        .map(|w| Rect {
+            x: w.x() as f64,
+            y: w.y() as f64,
+            width: w.width() as f64,
+            height: w.height() as f64,
+        })
+}
+
+/// Keeps `CaptureState::focused_window` up to date for window-relative
+/// coordinates on mouse payloads. Windows are only enumerated while such
+/// payloads can be emitted (armed, not paused, and mouse or pen events passing
+/// the event filter); otherwise the frame is cleared so it can't go stale.
+pub fn start_window_tracker(state: Arc<CaptureState>) {
+    thread::spawn(move || loop {
+        let tracking =
+            state.is_armed() && !state.is_paused() && emits_pointer_events(&state.settings());
+        let frame = if tracking {
+            frontmost_window_frame()
+        } else {
+            None
+        };
+        *state.focused_window.lock().unwrap() = frame;
+        thread::sleep(POLL_INTERVAL);
+    });
+}
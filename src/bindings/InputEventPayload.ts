@@ -0,0 +1,63 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * One overlay event. Recordings store these one per line, so the same type
+ * reads them back for replay. The overlay's TypeScript type is generated
+ * from this struct (`cargo test export_bindings` rewrites
+ * `src/bindings/InputEventPayload.ts`).
+ */
+export type InputEventPayload = { event_type: string, label: string, timestamp: number, 
+/**
+ * Milliseconds since the previously emitted event (opt-in via settings).
+ */
+delta_ms?: number, 
+/**
+ * Shared by every update of one continuous gesture; the overlay replaces
+ * the previous entry with the same id instead of adding a new one.
+ */
+gesture_id?: number, 
+/**
+ * Keyboard layout in effect when the event was captured, so exported or
+ * replayed events stay self-describing after a layout switch.
+ */
+layout?: string, 
+/**
+ * Cursor position in rdev screen coordinates, on mouse payloads.
+ */
+screen_x?: number, screen_y?: number, 
+/**
+ * Cursor position relative to the focused window's top-left corner, on
+ * mouse payloads when the window frame is known (see `window_tracker`).
+ */
+window_x?: number, window_y?: number, 
+/**
+ * On `drag` payloads: where the button went down, in screen coordinates.
+ * The end point is `screen_x`/`screen_y`.
+ */
+drag_start_x?: number, drag_start_y?: number, 
+/**
+ * On `pen` payloads: tip pressure from 0.0 to 1.0, and tilt in degrees.
+ */
+pressure?: number, tilt_x?: number, tilt_y?: number, 
+/**
+ * On raw passthrough payloads: the rdev key or button identifier
+ * (`KeyA`, `Left`) and the wheel delta in notches.
+ */
+key?: string, button?: string, delta_x?: number, delta_y?: number, 
+/**
+ * The rdev key or button behind the event (`KeyA`, `Unknown(135)`), when
+ * `include_raw` is on.
+ */
+raw?: string, 
+/**
+ * On shortcuts: how long each modifier (by label) had been held, in ms.
+ */
+modifier_hold_ms?: { [key in string]?: number }, 
+/**
+ * How long the overlay should keep this event, from `ttl_by_type`.
+ */
+ttl_ms?: number, 
+/**
+ * On `stats` payloads: key and mouse-button presses per minute.
+ */
+keys_per_minute?: number, clicks_per_minute?: number, };
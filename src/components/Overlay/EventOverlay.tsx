@@ -6,12 +6,16 @@ import {
   MousePointer2, Keyboard, Monitor, X,
   AlignLeft, AlignRight
 } from 'lucide-react';
+import type { InputEventPayload as GeneratedPayload } from '../../bindings/InputEventPayload';
 
-interface InputEventPayload {
-  event_type: 'mousemove' | 'mousedown' | 'mouseup' | 'click' | 'doubleclick' | 'key' | 'system' | 'dragstart' | 'drag';
-  label: string;
-  timestamp: number;
-}
+type EventType = 'mousemove' | 'mousedown' | 'mouseup' | 'click' | 'doubleclick' | 'tripleclick' | 'key' | 'shortcut' | 'keyup' | 'keyhold' | 'system' | 'dragstart' | 'drag' | 'scrolled' | 'paste' | 'scroll' | 'dwell' | 'caption' | 'pen' | 'mousechord' | 'keydown' | 'wheel' | 'stats' | 'text' | 'clear' | 'idle';
+
+// Generated from the Rust struct; only the event type is narrowed here
+type InputEventPayload = Omit<GeneratedPayload, 'event_type'> & {
+  event_type: EventType;
+  // Set by the overlay on arrival; `timestamp` may not be wall-clock time
+  received_at?: number;
+};
 
 interface LogItem {
   id: number;
@@ -20,9 +24,42 @@ interface LogItem {
   isTypingSequence?: boolean;
 }
 
+interface RecordingState {
+  active: boolean;
+  color: string;
+}
+
+interface CaptureHealth {
+  listener_running: boolean;
+  stopped: boolean;
+  hook_responding: boolean;
+  ms_since_last_input?: number;
+  last_error?: string;
+  events_dropped: number;
+}
+
+interface CaptureBackendStatus {
+  session_type?: string;
+  supported: boolean;
+  message?: string;
+}
+
+interface CaptureStatus {
+  paused: boolean;
+  armed: boolean;
+  recording: boolean;
+  replaying: boolean;
+  stopped: boolean;
+  double_click_ms: number;
+  drag_px: number;
+  mousemove_throttle_ms: number;
+  layout?: string;
+  pause_hotkey: string;
+}
+
 interface AppSettings {
   position: 'left' | 'right';
-  keyboardLayout: 'US' | 'JIS';
+  keyboardLayout: 'US' | 'JIS' | 'AZERTY';
   doubleClickThreshold: number;
   monitorName?: string;
 }
@@ -30,6 +67,8 @@ interface AppSettings {
 export default function EventOverlay() {
   const [logs, setLogs] = useState<LogItem[]>([]);
   const [permission, setPermission] = useState<boolean | null>(null);
+  const [health, setHealth] = useState<CaptureHealth | null>(null);
+  const [backend, setBackend] = useState<CaptureBackendStatus | null>(null);
   const logsEndRef = useRef<HTMLDivElement>(null);
 
   const [monitors, setMonitors] = useState<TauriMonitor[]>([]);
@@ -38,7 +77,8 @@ export default function EventOverlay() {
   // Settings
   const [filters, setFilters] = useState({
     mousemove: true, mousedown: true, mouseup: true, click: true,
-    doubleclick: true, key: true, system: true, drag: true, dragstart: false
+    doubleclick: true, tripleclick: true, key: true, shortcut: true, keyup: true, keyhold: true, system: true, drag: true, dragstart: false,
+    scrolled: true, paste: true, scroll: true, dwell: true, caption: true, pen: true, mousechord: true, keydown: true, wheel: true, stats: true, text: true
   });
   const [settings, setSettings] = useState<AppSettings>(() => {
     const saved = localStorage.getItem('echocast-settings');
@@ -46,15 +86,38 @@ export default function EventOverlay() {
   });
 
   const [showSettings, setShowSettings] = useState(false);
+  const [recording, setRecording] = useState<RecordingState>({ active: false, color: '#ef4444' });
+  const [replaying, setReplaying] = useState(false);
+  const [stats, setStats] = useState<InputEventPayload | null>(null);
+  const [idle, setIdle] = useState(false);
   const showSettingsRef = useRef(showSettings);
 
   useEffect(() => {
     showSettingsRef.current = showSettings;
   }, [showSettings]);
 
+  // The backend persists its settings; start from its values, not localStorage
+  const backendSynced = useRef(false);
+  useEffect(() => {
+    invoke<CaptureStatus>('get_capture_state').then(status => {
+      backendSynced.current = true;
+      setReplaying(status.replaying);
+      setSettings(prev => ({
+        ...prev,
+        doubleClickThreshold: status.double_click_ms,
+        keyboardLayout: (status.layout?.toUpperCase() as AppSettings['keyboardLayout'] | undefined) ?? prev.keyboardLayout,
+      }));
+    }).catch(e => {
+      backendSynced.current = true;
+      console.error("Failed to read capture state", e);
+    });
+  }, []);
+
   useEffect(() => {
     localStorage.setItem('echocast-settings', JSON.stringify(settings));
-    // TODO: Send settings to Rust backend here (especially keyboard layout and double click threshold)
+    if (!backendSynced.current) return;
+    invoke('set_keyboard_layout', { layout: settings.keyboardLayout }).catch(console.error);
+    invoke('set_double_click_threshold', { ms: settings.doubleClickThreshold }).catch(console.error);
   }, [settings]);
 
   // Initial permission check
@@ -113,6 +176,14 @@ export default function EventOverlay() {
     }
   }, [showSettings, settings.position]);
 
+  // Recording indicator
+  useEffect(() => {
+    const unlistenPromise = listen<RecordingState>('recording-state', (event) => setRecording(event.payload));
+    return () => {
+      unlistenPromise.then(u => u());
+    };
+  }, []);
+
   // Listen for toggle-settings shortcut
   useEffect(() => {
     const unlistenTogglePromise = listen('toggle-settings', () => {
@@ -140,11 +211,36 @@ export default function EventOverlay() {
 
   useEffect(() => {
     const unlistenPromise = listen<InputEventPayload>('input-event', (event) => {
+      const newEvent = { ...event.payload, received_at: Date.now() };
+      // The replay badge follows its system events even while settings are open
+      if (newEvent.event_type === 'system') {
+        if (newEvent.label === 'Replay Started') setReplaying(true);
+        if (newEvent.label === 'Replay Finished') setReplaying(false);
+      }
+
+      // A flush signal from the backend, not an entry; applies even while settings are open
+      if (newEvent.event_type === 'clear') {
+        setLogs([]);
+        setStats(null);
+        return;
+      }
+
+      // The idle signal fades the overlay out until input resumes
+      if (newEvent.event_type === 'idle') {
+        setIdle(true);
+        return;
+      }
+      if (newEvent.event_type !== 'system' && newEvent.event_type !== 'stats') setIdle(false);
+
+      // The activity meter is a standing readout, not a log entry
+      if (newEvent.event_type === 'stats') {
+        setStats(newEvent);
+        return;
+      }
+
       // Pause log updates while settings are open
       if (showSettingsRef.current) return;
 
-      const newEvent = event.payload;
-
       setLogs(prevLogs => {
         const lastLog = prevLogs[prevLogs.length - 1];
 
@@ -153,6 +249,16 @@ export default function EventOverlay() {
           return [...prevLogs.slice(0, -1), { ...lastLog, payload: newEvent, count: lastLog.count + 1 }];
         }
 
+        // Gesture updates (e.g. inertial scroll) replace the entry they belong to
+        if (newEvent.gesture_id !== undefined && lastLog?.payload.gesture_id === newEvent.gesture_id) {
+          return [...prevLogs.slice(0, -1), { ...lastLog, payload: newEvent }];
+        }
+
+        // Repeated shortcut
+        if (newEvent.event_type === 'shortcut' && lastLog?.payload.event_type === 'shortcut' && lastLog.payload.label === newEvent.label) {
+          return [...prevLogs.slice(0, -1), { ...lastLog, count: lastLog.count + 1 }];
+        }
+
         // Key combo optimization
         if (newEvent.event_type === 'key') {
           const newLabel = newEvent.label.replace(/^@key\[/i, '').replace(/\]$/, '');
@@ -166,7 +272,7 @@ export default function EventOverlay() {
           if (lastLog?.payload.event_type === 'key' && isTextKey(newLabel) && lastLog.isTypingSequence) {
             const currentContent = lastLabel;
             const updatedLabel = `@Key[${currentContent}${newLabel}]`;
-            return [...prevLogs.slice(0, -1), { ...lastLog, payload: { ...lastLog.payload, label: updatedLabel, timestamp: newEvent.timestamp }, count: 1 }];
+            return [...prevLogs.slice(0, -1), { ...lastLog, payload: { ...lastLog.payload, label: updatedLabel, timestamp: newEvent.timestamp, received_at: newEvent.received_at }, count: 1 }];
           }
 
           const isNewTypingSequence = isTextKey(newLabel);
@@ -180,7 +286,8 @@ export default function EventOverlay() {
           const btnMatch = newEvent.label.match(/\[(.*?)\]/);
           const btnName = btnMatch ? btnMatch[1] : null;
           if (btnName) {
-            const clickPattern = new RegExp(`\\[${btnName}\\]`);
+            // mousedown labels may carry the press position: [Left @ 320,450]
+            const clickPattern = new RegExp(`\\[${btnName}( @ [^\\]]*)?\\]`);
             const cleanedLogs = prevLogs.filter(log => {
               if (newEvent.timestamp - log.payload.timestamp > 500) return true;
               const type = log.payload.event_type;
@@ -191,16 +298,17 @@ export default function EventOverlay() {
           }
         }
 
-        // DoubleClick Cleanup Logic
-        if (newEvent.event_type === 'doubleclick') {
+        // DoubleClick / TripleClick Cleanup Logic
+        if (newEvent.event_type === 'doubleclick' || newEvent.event_type === 'tripleclick') {
           const btnMatch = newEvent.label.match(/\[(.*?)\]/);
           const btnName = btnMatch ? btnMatch[1] : null;
           if (btnName) {
-            const clickPattern = new RegExp(`\\[${btnName}\\]`);
+            // mousedown labels may carry the press position: [Left @ 320,450]
+            const clickPattern = new RegExp(`\\[${btnName}( @ [^\\]]*)?\\]`);
             const cleanedLogs = prevLogs.filter(log => {
               if (newEvent.timestamp - log.payload.timestamp > 500) return true;
               const type = log.payload.event_type;
-              if (['click', 'mousedown', 'mouseup'].includes(type) && log.payload.label.match(clickPattern)) return false;
+              if (['click', 'doubleclick', 'mousedown', 'mouseup'].includes(type) && log.payload.label.match(clickPattern)) return false;
               return true;
             });
             return [...cleanedLogs, { id: Date.now() + Math.random(), payload: newEvent, count: 1 }];
@@ -219,6 +327,43 @@ export default function EventOverlay() {
     };
   }, []);
 
+  // Restore recent history after a reload; live events that already arrived win
+  useEffect(() => {
+    invoke<InputEventPayload[]>('get_recent_events', { count: 20 })
+      .then(events => {
+        const receivedAt = Date.now();
+        setLogs(prevLogs => prevLogs.length > 0 ? prevLogs : events.map(payload => (
+          { id: receivedAt + Math.random(), payload: { ...payload, received_at: receivedAt }, count: 1 }
+        )));
+      })
+      .catch(e => console.error("Failed to restore recent events", e));
+  }, []);
+
+  // The session type can't change while running, so one check is enough
+  useEffect(() => {
+    invoke<CaptureBackendStatus>('capture_backend_status').then(setBackend).catch(e => console.error("Failed to read capture backend status", e));
+  }, []);
+
+  // Poll capture health so a dead listener doesn't go unnoticed
+  useEffect(() => {
+    const poll = () => invoke<CaptureHealth>('capture_health').then(setHealth).catch(e => console.error("Failed to read capture health", e));
+    poll();
+    const timer = setInterval(poll, 5000);
+    return () => clearInterval(timer);
+  }, []);
+
+  // Drop entries whose per-type TTL (sent by the backend) has elapsed
+  useEffect(() => {
+    const timer = setInterval(() => {
+      const now = Date.now();
+      setLogs(prevLogs => {
+        const next = prevLogs.filter(log => log.payload.ttl_ms === undefined || now - (log.payload.received_at ?? log.payload.timestamp) < log.payload.ttl_ms);
+        return next.length === prevLogs.length ? prevLogs : next;
+      });
+    }, 250);
+    return () => clearInterval(timer);
+  }, []);
+
   useEffect(() => {
     logsEndRef.current?.scrollIntoView({ behavior: "smooth" });
   }, [logs]);
@@ -230,7 +375,9 @@ export default function EventOverlay() {
       case 'mouseup': return <MousePointer2 className="w-4 h-4 text-blue-300 shrink-0" />;
       case 'click': return <MousePointer2 className="w-4 h-4 text-green-400 shrink-0" />;
       case 'doubleclick': return <div className="flex"><MousePointer2 className="w-4 h-4 text-purple-400 shrink-0" /><MousePointer2 className="w-4 h-4 text-purple-400 shrink-0 -ml-2" /></div>;
+      case 'tripleclick': return <div className="flex"><MousePointer2 className="w-4 h-4 text-fuchsia-400 shrink-0" /><MousePointer2 className="w-4 h-4 text-fuchsia-400 shrink-0 -ml-2" /><MousePointer2 className="w-4 h-4 text-fuchsia-400 shrink-0 -ml-2" /></div>;
       case 'key': return <Keyboard className="w-4 h-4 text-yellow-400 shrink-0" />;
+      case 'shortcut': return <Keyboard className="w-4 h-4 text-amber-300 shrink-0" />;
       case 'system': return <Monitor className="w-4 h-4 text-red-400 shrink-0" />;
       case 'dragstart': return <MousePointer2 className="w-4 h-4 text-pink-400 shrink-0 opacity-50" />;
       case 'drag': return <MousePointer2 className="w-4 h-4 text-pink-500 shrink-0" />;
@@ -245,7 +392,9 @@ export default function EventOverlay() {
       case 'mouseup': return 'border-l-4 border-l-blue-500';
       case 'click': return 'border-l-4 border-l-green-500';
       case 'doubleclick': return 'border-l-4 border-l-purple-500 ring-1 ring-purple-500/30';
+      case 'tripleclick': return 'border-l-4 border-l-fuchsia-500 ring-1 ring-fuchsia-500/30';
       case 'key': return 'border-l-4 border-l-yellow-500';
+      case 'shortcut': return 'border-l-4 border-l-amber-400 ring-1 ring-amber-400/30';
       case 'system': return 'border-l-4 border-l-red-500 bg-red-950/30';
       case 'dragstart': return 'border-l-4 border-l-pink-500/50';
       case 'drag': return 'border-l-4 border-l-pink-500 ring-1 ring-pink-500/30';
@@ -267,7 +416,52 @@ export default function EventOverlay() {
   const slideAnimation = settings.position === 'right' ? 'slide-in-from-right-8' : 'slide-in-from-left-8';
 
   return (
-    <div className={`fixed bottom-4 ${containerPosition} flex flex-col gap-2 pointer-events-none transition-all duration-300`}>
+    <div className={`fixed bottom-4 ${containerPosition} flex flex-col gap-2 pointer-events-none transition-all duration-300 ${idle && !showSettings ? 'opacity-0' : ''}`}>
+      {/* Recording Indicator */}
+      {recording.active && (
+        <div className="flex items-center gap-2 text-xs text-white/80 select-none">
+          <span className="w-3 h-3 rounded-full animate-pulse" style={{ backgroundColor: recording.color }} />
+          REC
+        </div>
+      )}
+
+      {/* Replay Indicator */}
+      {replaying && (
+        <div className="flex items-center gap-2 text-xs text-white/80 select-none">
+          <span className="w-3 h-3 rounded-full animate-pulse bg-blue-400" />
+          Replaying
+        </div>
+      )}
+
+      {/* Activity Meter */}
+      {stats && filters.stats && Date.now() - (stats.received_at ?? stats.timestamp) < 3000 && (
+        <div className="flex items-center gap-2 text-xs text-white/80 select-none font-mono">
+          {stats.keys_per_minute ?? 0} KPM · {stats.clicks_per_minute ?? 0} CPM
+        </div>
+      )}
+
+      {/* Capture Off Indicator */}
+      {health?.stopped && (
+        <div className="flex items-center gap-2 text-xs text-white/80 select-none">
+          <span className="w-3 h-3 rounded-full bg-gray-400" />
+          Capture off
+        </div>
+      )}
+
+      {/* Capture Health Warning */}
+      {health && !health.stopped && !health.listener_running && (
+        <div className="bg-red-500/80 text-white p-3 rounded-lg mb-2 backdrop-blur-sm">
+          ⚠️ Input capture stopped{health.last_error ? `: ${health.last_error}` : ''}
+        </div>
+      )}
+
+      {/* Unsupported Session Warning (e.g. Wayland) */}
+      {backend && !backend.supported && (
+        <div className="bg-red-500/80 text-white p-3 rounded-lg mb-2 backdrop-blur-sm pointer-events-auto">
+          ⚠️ {backend.message}
+        </div>
+      )}
+
       {/* Permission Warning */}
       {permission === false && (
         <div className="bg-red-500/80 text-white p-3 rounded-lg mb-2 backdrop-blur-sm pointer-events-auto cursor-pointer" onClick={() => invoke('request_accessibility_permission')}>
@@ -342,6 +536,12 @@ export default function EventOverlay() {
                 >
                   JIS (ISO)
                 </button>
+                <button
+                  onClick={() => setSettings(s => ({ ...s, keyboardLayout: 'AZERTY' }))}
+                  className={`flex-1 py-1.5 rounded-md transition-all ${settings.keyboardLayout === 'AZERTY' ? 'bg-blue-600 shadow-sm text-white' : 'hover:bg-white/5 text-gray-400'}`}
+                >
+                  AZERTY
+                </button>
               </div>
             </div>
 
@@ -392,6 +592,7 @@ export default function EventOverlay() {
                   { key: 'click', label: 'Click', icon: MousePointer2, color: 'text-green-400' },
                   { key: 'doubleclick', label: 'Dbl Click', icon: MousePointer2, color: 'text-purple-400' },
                   { key: 'key', label: 'Keyboard', icon: Keyboard, color: 'text-yellow-400' },
+                  { key: 'shortcut', label: 'Shortcut', icon: Keyboard, color: 'text-amber-300' },
                   { key: 'drag', label: 'Drag', icon: MousePointer2, color: 'text-pink-500' },
                   { key: 'system', label: 'System', icon: Monitor, color: 'text-red-400' },
                 ].map(({ key, label, icon: Icon, color }) => (